@@ -0,0 +1,570 @@
+pub mod format;
+pub mod unparse;
+pub mod visitor;
+
+use std::fmt;
+use std::fmt::Display;
+use std::rc::Rc;
+
+use crate::token::number::Number;
+use crate::token::{Span, Token};
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum LiteralValue {
+    Number(Number),
+    String(String),
+    Bool(bool),
+    Nil,
+}
+
+// Identifies a node within the tree it was parsed into: its index in the
+// tree's arena. Passes keep what they learn about a node in a `NodeMap`
+// keyed by its id rather than in the tree itself.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord)]
+pub struct NodeId(pub u32);
+
+impl NodeId {
+    pub fn index(&self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl Display for NodeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Node {
+    Expr(Expr),
+    Stmt(Stmt),
+}
+
+impl Node {
+    pub fn id(&self) -> NodeId {
+        match self {
+            Node::Expr(expr) => expr.id,
+            Node::Stmt(stmt) => stmt.id,
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            Node::Expr(expr) => expr.span,
+            Node::Stmt(stmt) => stmt.span,
+        }
+    }
+}
+
+// Every node of a syntax tree, stored by id, and the ids of its roots: the
+// top-level statements of a program, or the one expression of a parsed
+// expression. Nodes refer to their children by id.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Ast {
+    nodes: Vec<Node>,
+    roots: Vec<NodeId>,
+}
+
+impl Ast {
+    pub fn new() -> Self {
+        Ast::default()
+    }
+
+    pub fn add_expr(&mut self, span: Span, kind: ExprKind) -> NodeId {
+        let id = self.next_id();
+        self.nodes.push(Node::Expr(Expr { id, span, kind }));
+        id
+    }
+
+    pub fn add_stmt(&mut self, span: Span, kind: StmtKind) -> NodeId {
+        let id = self.next_id();
+        self.nodes.push(Node::Stmt(Stmt { id, span, kind }));
+        id
+    }
+
+    fn next_id(&self) -> NodeId {
+        NodeId(self.nodes.len() as u32)
+    }
+
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id.index()]
+    }
+
+    // Panics if `id` is a statement: children of the wrong kind cannot be
+    // built through the parser.
+    pub fn expr(&self, id: NodeId) -> &Expr {
+        match self.node(id) {
+            Node::Expr(expr) => expr,
+            Node::Stmt(_) => panic!("node {} is a statement, not an expression", id),
+        }
+    }
+
+    pub fn expr_mut(&mut self, id: NodeId) -> &mut Expr {
+        match &mut self.nodes[id.index()] {
+            Node::Expr(expr) => expr,
+            Node::Stmt(_) => panic!("node {} is a statement, not an expression", id),
+        }
+    }
+
+    pub fn stmt(&self, id: NodeId) -> &Stmt {
+        match self.node(id) {
+            Node::Stmt(stmt) => stmt,
+            Node::Expr(_) => panic!("node {} is an expression, not a statement", id),
+        }
+    }
+
+    pub fn stmt_mut(&mut self, id: NodeId) -> &mut Stmt {
+        match &mut self.nodes[id.index()] {
+            Node::Stmt(stmt) => stmt,
+            Node::Expr(_) => panic!("node {} is an expression, not a statement", id),
+        }
+    }
+
+    pub fn span(&self, id: NodeId) -> Span {
+        self.node(id).span()
+    }
+
+    pub fn roots(&self) -> &[NodeId] {
+        &self.roots
+    }
+
+    pub fn set_roots(&mut self, roots: Vec<NodeId>) {
+        self.roots = roots;
+    }
+
+    // The expression of a tree made by parsing an expression.
+    pub fn root(&self) -> NodeId {
+        self.roots[0]
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = &Node> {
+        self.nodes.iter()
+    }
+
+    // Prints a node in the book's parenthesized prefix form:
+    // `(+ 1.0 (group 2.0))`.
+    pub fn display(&self, id: NodeId) -> Printed<'_> {
+        Printed { ast: self, id }
+    }
+}
+
+// A side table with one optional slot per node, stored densely by id.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeMap<T> {
+    slots: Vec<Option<T>>,
+}
+
+impl<T> Default for NodeMap<T> {
+    fn default() -> Self {
+        NodeMap { slots: Vec::new() }
+    }
+}
+
+impl<T> NodeMap<T> {
+    pub fn new() -> Self {
+        NodeMap::default()
+    }
+
+    pub fn insert(&mut self, id: NodeId, value: T) -> Option<T> {
+        if id.index() >= self.slots.len() {
+            self.slots.resize_with(id.index() + 1, || None);
+        }
+        self.slots[id.index()].replace(value)
+    }
+
+    pub fn get(&self, id: NodeId) -> Option<&T> {
+        self.slots.get(id.index()).and_then(Option::as_ref)
+    }
+
+    pub fn get_mut(&mut self, id: NodeId) -> Option<&mut T> {
+        self.slots.get_mut(id.index()).and_then(Option::as_mut)
+    }
+
+    pub fn contains(&self, id: NodeId) -> bool {
+        self.get(id).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.iter().filter(|slot| slot.is_some()).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (NodeId, &T)> {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(|(i, slot)| slot.as_ref().map(|value| (NodeId(i as u32), value)))
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Expr {
+    pub id: NodeId,
+    pub span: Span,
+    pub kind: ExprKind,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ExprKind {
+    Literal {
+        value: LiteralValue,
+    },
+    Grouping {
+        expr: NodeId,
+    },
+    Unary {
+        operator: Token,
+        right: NodeId,
+    },
+    Binary {
+        left: NodeId,
+        operator: Token,
+        right: NodeId,
+    },
+    // `and` and `or`, which short-circuit
+    Logical {
+        left: NodeId,
+        operator: Token,
+        right: NodeId,
+    },
+    // `condition ? then_branch : else_branch`, in the extended dialect
+    Conditional {
+        condition: NodeId,
+        then_branch: NodeId,
+        else_branch: NodeId,
+    },
+    Variable {
+        name: Token,
+    },
+    Assign {
+        name: Token,
+        value: NodeId,
+    },
+    Call {
+        callee: NodeId,
+        // the closing parenthesis, used to locate errors
+        paren: Token,
+        arguments: Vec<NodeId>,
+    },
+    Get {
+        object: NodeId,
+        name: Token,
+    },
+    Set {
+        object: NodeId,
+        name: Token,
+        value: NodeId,
+    },
+    This {
+        keyword: Token,
+    },
+    Super {
+        keyword: Token,
+        method: Token,
+    },
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct FunctionDecl {
+    pub name: Token,
+    pub params: Vec<Token>,
+    pub body: Vec<NodeId>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Stmt {
+    pub id: NodeId,
+    pub span: Span,
+    pub kind: StmtKind,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum StmtKind {
+    Expression {
+        expr: NodeId,
+    },
+    Print {
+        expr: NodeId,
+    },
+    Var {
+        name: Token,
+        initializer: Option<NodeId>,
+    },
+    Block {
+        statements: Vec<NodeId>,
+    },
+    If {
+        condition: NodeId,
+        then_branch: NodeId,
+        else_branch: Option<NodeId>,
+    },
+    While {
+        condition: NodeId,
+        body: NodeId,
+    },
+    // shared with the functions made from it at runtime
+    Function(Rc<FunctionDecl>),
+    Return {
+        keyword: Token,
+        value: Option<NodeId>,
+    },
+    Class {
+        name: Token,
+        // always an `ExprKind::Variable`
+        superclass: Option<NodeId>,
+        methods: Vec<Rc<FunctionDecl>>,
+    },
+}
+
+impl Display for LiteralValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LiteralValue::Number(num) => write!(f, "{}", num),
+            LiteralValue::String(value) => write!(f, "{}", value),
+            LiteralValue::Bool(value) => write!(f, "{}", value),
+            LiteralValue::Nil => write!(f, "nil"),
+        }
+    }
+}
+
+pub struct Printed<'a> {
+    ast: &'a Ast,
+    id: NodeId,
+}
+
+impl Printed<'_> {
+    fn child(&self, id: NodeId) -> Self {
+        Printed { ast: self.ast, id }
+    }
+
+    fn list(&self, f: &mut fmt::Formatter<'_>, ids: &[NodeId]) -> fmt::Result {
+        for id in ids {
+            write!(f, " {}", self.child(*id))?;
+        }
+        Ok(())
+    }
+
+    fn function(&self, f: &mut fmt::Formatter<'_>, function: &FunctionDecl) -> fmt::Result {
+        let params: Vec<&str> = function.params.iter().map(|p| p.lexeme.as_str()).collect();
+        write!(f, "(fun {} ({})", function.name.lexeme, params.join(" "))?;
+        self.list(f, &function.body)?;
+        write!(f, ")")
+    }
+
+    fn expr(&self, f: &mut fmt::Formatter<'_>, expr: &Expr) -> fmt::Result {
+        let child = |id: &NodeId| self.child(*id);
+        match &expr.kind {
+            ExprKind::Literal { value } => write!(f, "{}", value),
+            ExprKind::Grouping { expr } => write!(f, "(group {})", child(expr)),
+            ExprKind::Unary { operator, right } => {
+                write!(f, "({} {})", operator.lexeme, child(right))
+            }
+            ExprKind::Binary {
+                left,
+                operator,
+                right,
+            }
+            | ExprKind::Logical {
+                left,
+                operator,
+                right,
+            } => write!(f, "({} {} {})", operator.lexeme, child(left), child(right)),
+            ExprKind::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+            } => write!(
+                f,
+                "(?: {} {} {})",
+                child(condition),
+                child(then_branch),
+                child(else_branch)
+            ),
+            ExprKind::Variable { name } => write!(f, "{}", name.lexeme),
+            ExprKind::Assign { name, value } => {
+                write!(f, "(= {} {})", name.lexeme, child(value))
+            }
+            ExprKind::Call {
+                callee, arguments, ..
+            } => {
+                write!(f, "(call {}", child(callee))?;
+                self.list(f, arguments)?;
+                write!(f, ")")
+            }
+            ExprKind::Get { object, name } => {
+                write!(f, "(. {} {})", child(object), name.lexeme)
+            }
+            ExprKind::Set {
+                object,
+                name,
+                value,
+            } => write!(
+                f,
+                "(= (. {} {}) {})",
+                child(object),
+                name.lexeme,
+                child(value)
+            ),
+            ExprKind::This { .. } => write!(f, "this"),
+            ExprKind::Super { method, .. } => write!(f, "(super {})", method.lexeme),
+        }
+    }
+
+    fn stmt(&self, f: &mut fmt::Formatter<'_>, stmt: &Stmt) -> fmt::Result {
+        let child = |id: &NodeId| self.child(*id);
+        match &stmt.kind {
+            StmtKind::Expression { expr } => write!(f, "(; {})", child(expr)),
+            StmtKind::Print { expr } => write!(f, "(print {})", child(expr)),
+            StmtKind::Var {
+                name,
+                initializer: Some(initializer),
+            } => write!(f, "(var {} {})", name.lexeme, child(initializer)),
+            StmtKind::Var {
+                name,
+                initializer: None,
+            } => write!(f, "(var {})", name.lexeme),
+            StmtKind::Block { statements } => {
+                write!(f, "(block")?;
+                self.list(f, statements)?;
+                write!(f, ")")
+            }
+            StmtKind::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                write!(f, "(if {} {}", child(condition), child(then_branch))?;
+                if let Some(else_branch) = else_branch {
+                    write!(f, " {}", child(else_branch))?;
+                }
+                write!(f, ")")
+            }
+            StmtKind::While { condition, body } => {
+                write!(f, "(while {} {})", child(condition), child(body))
+            }
+            StmtKind::Function(function) => self.function(f, function),
+            StmtKind::Return {
+                value: Some(value), ..
+            } => write!(f, "(return {})", child(value)),
+            StmtKind::Return { value: None, .. } => write!(f, "(return)"),
+            StmtKind::Class {
+                name,
+                superclass,
+                methods,
+            } => {
+                write!(f, "(class {}", name.lexeme)?;
+                if let Some(superclass) = superclass {
+                    write!(f, " (< {})", child(superclass))?;
+                }
+                for method in methods {
+                    write!(f, " ")?;
+                    self.function(f, method)?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+impl Display for Printed<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.ast.node(self.id) {
+            Node::Expr(expr) => self.expr(f, expr),
+            Node::Stmt(stmt) => self.stmt(f, stmt),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse_program;
+    use visitor::{walk_expr, walk_stmt, walk_stmts, Walker};
+
+    #[test]
+    fn test_node_ids_are_unique() {
+        struct Ids(Vec<NodeId>);
+
+        impl Walker for Ids {
+            fn visit_stmt(&mut self, ast: &Ast, stmt: &Stmt) {
+                self.0.push(stmt.id);
+                walk_stmt(self, ast, stmt);
+            }
+
+            fn visit_expr(&mut self, ast: &Ast, expr: &Expr) {
+                self.0.push(expr.id);
+                walk_expr(self, ast, expr);
+            }
+        }
+
+        let ast = parse_program("var a = 1 + 2;\nfor (;;) print a;\na = a + 1;")
+            .ast
+            .unwrap();
+        let mut ids = Ids(Vec::new());
+        walk_stmts(&mut ids, &ast, ast.roots());
+        // var, 1 + 2 and its operands, then while, true, print and a, then
+        // the assignment statement, a = and a + 1 and its operands
+        assert_eq!(13, ids.0.len());
+        let mut unique = ids.0.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(ids.0.len(), unique.len());
+        // every node in the arena is in the tree, each under its own id
+        assert_eq!(ast.len(), ids.0.len());
+        assert!(ast
+            .nodes()
+            .enumerate()
+            .all(|(i, node)| node.id().index() == i));
+    }
+
+    #[test]
+    fn test_arena_lookups() {
+        let ast = parse_program("var a = 1 + 2;\nfor (;;) print a;")
+            .ast
+            .unwrap();
+        let [var, while_loop] = ast.roots() else {
+            panic!("expected two statements");
+        };
+        assert_eq!(Span::new(0, 14), ast.span(*var));
+        // the loop desugars to a while statement spanning the whole `for`
+        assert_eq!(Span::new(15, 32), ast.span(*while_loop));
+        let StmtKind::Var {
+            initializer: Some(initializer),
+            ..
+        } = ast.stmt(*var).kind
+        else {
+            panic!("expected a var declaration");
+        };
+        assert_eq!(Span::new(8, 13), ast.span(initializer));
+        assert_eq!("(+ 1.0 2.0)", ast.display(initializer).to_string());
+        assert_eq!(
+            "(while true (print a))",
+            ast.display(*while_loop).to_string()
+        );
+    }
+
+    #[test]
+    fn test_node_map() {
+        let mut types: NodeMap<&str> = NodeMap::new();
+        assert!(types.is_empty());
+        types.insert(NodeId(3), "number");
+        assert_eq!(Some("number"), types.insert(NodeId(3), "string"));
+        assert_eq!(Some(&"string"), types.get(NodeId(3)));
+        assert!(!types.contains(NodeId(0)));
+        assert_eq!(None, types.get(NodeId(10)));
+        *types.get_mut(NodeId(3)).unwrap() = "bool";
+        assert_eq!(vec![(NodeId(3), &"bool")], types.iter().collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,747 @@
+use std::fmt;
+use std::fmt::Display;
+use std::rc::Rc;
+use std::str::FromStr;
+
+use super::Node as AstNode;
+use super::{Ast, Expr, ExprKind, FunctionDecl, LiteralValue, NodeId, Stmt, StmtKind};
+use crate::json::{sexpr, Json};
+use crate::token::number::Number;
+use crate::token::{Span, Token};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum AstFormat {
+    // the parenthesized prefix form of the book, `(+ 1.0 2.0)`
+    #[default]
+    Book,
+    // the full tree as by `to_json`, written as S-expressions
+    Sexpr,
+    Json,
+    // Lox source again, see `unparse`
+    Lox,
+}
+
+impl FromStr for AstFormat {
+    type Err = String;
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "book" => Ok(AstFormat::Book),
+            "sexpr" => Ok(AstFormat::Sexpr),
+            "json" => Ok(AstFormat::Json),
+            "lox" => Ok(AstFormat::Lox),
+            other => Err(format!(
+                "unknown syntax tree format: {} (expected book, sexpr, json or lox)",
+                other
+            )),
+        }
+    }
+}
+
+impl Display for AstFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let format = match self {
+            AstFormat::Book => "book",
+            AstFormat::Sexpr => "sexpr",
+            AstFormat::Json => "json",
+            AstFormat::Lox => "lox",
+        };
+        write!(f, "{}", format)
+    }
+}
+
+fn span_to_json(span: Span) -> Json {
+    Json::Array(vec![
+        Json::Number(span.start as f64),
+        Json::Number(span.end as f64),
+    ])
+}
+
+fn node<const N: usize>(kind: &str, fields: [(&str, Json); N]) -> Json {
+    let mut object = vec![("kind", Json::string(kind))];
+    object.extend(fields);
+    Json::object(object)
+}
+
+// Puts the id and span of a node right after its kind.
+fn with_position(json: Json, id: NodeId, span: Span) -> Json {
+    match json {
+        Json::Object(mut fields) => {
+            let position = [
+                (String::from("id"), Json::Number(id.0 as f64)),
+                (String::from("span"), span_to_json(span)),
+            ];
+            fields.splice(1..1, position);
+            Json::Object(fields)
+        }
+        other => other,
+    }
+}
+
+fn optional<T>(value: Option<&T>, to_json: impl Fn(&T) -> Json) -> Json {
+    value.map_or(Json::Null, to_json)
+}
+
+fn list<T>(items: &[T], to_json: impl Fn(&T) -> Json) -> Json {
+    Json::Array(items.iter().map(to_json).collect())
+}
+
+impl LiteralValue {
+    pub fn to_json(&self) -> Json {
+        match self {
+            LiteralValue::Number(num) => Json::Number(num.value()),
+            LiteralValue::String(value) => Json::string(value.as_str()),
+            LiteralValue::Bool(value) => Json::Bool(*value),
+            LiteralValue::Nil => Json::Null,
+        }
+    }
+
+    pub fn from_json(json: &Json) -> Result<LiteralValue, String> {
+        match json {
+            Json::Number(num) => Ok(LiteralValue::Number(Number::from(*num))),
+            Json::String(value) => Ok(LiteralValue::String(value.clone())),
+            Json::Bool(value) => Ok(LiteralValue::Bool(*value)),
+            Json::Null => Ok(LiteralValue::Nil),
+            other => Err(format!("not a literal value: {}", other)),
+        }
+    }
+}
+
+// Every node is an object with its `kind`, the name of the Rust variant,
+// its `id` and `span`, and one field per child, nested in place. Tokens are
+// written as by `Token::to_json`, so they carry their own positions.
+impl Ast {
+    pub fn to_json(&self, id: NodeId) -> Json {
+        match self.node(id) {
+            AstNode::Expr(expr) => self.expr_to_json(expr),
+            AstNode::Stmt(stmt) => self.stmt_to_json(stmt),
+        }
+    }
+
+    // The same tree as `to_json`, e.g. `(Literal :id 0 :span [0 3] :value 1)`.
+    pub fn to_sexpr(&self, id: NodeId) -> String {
+        sexpr::pretty(&self.to_json(id))
+    }
+
+    fn children_to_json(&self, ids: &[NodeId]) -> Json {
+        list(ids, |id| self.to_json(*id))
+    }
+
+    fn expr_to_json(&self, expr: &Expr) -> Json {
+        let child = |id: &NodeId| self.to_json(*id);
+        let json = match &expr.kind {
+            ExprKind::Literal { value, .. } => node("Literal", [("value", value.to_json())]),
+            ExprKind::Grouping { expr, .. } => node("Grouping", [("expr", child(expr))]),
+            ExprKind::Unary { operator, right } => node(
+                "Unary",
+                [("operator", operator.to_json()), ("right", child(right))],
+            ),
+            ExprKind::Binary {
+                left,
+                operator,
+                right,
+            } => node(
+                "Binary",
+                [
+                    ("left", child(left)),
+                    ("operator", operator.to_json()),
+                    ("right", child(right)),
+                ],
+            ),
+            ExprKind::Logical {
+                left,
+                operator,
+                right,
+            } => node(
+                "Logical",
+                [
+                    ("left", child(left)),
+                    ("operator", operator.to_json()),
+                    ("right", child(right)),
+                ],
+            ),
+            ExprKind::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+            } => node(
+                "Conditional",
+                [
+                    ("condition", child(condition)),
+                    ("then_branch", child(then_branch)),
+                    ("else_branch", child(else_branch)),
+                ],
+            ),
+            ExprKind::Variable { name } => node("Variable", [("name", name.to_json())]),
+            ExprKind::Assign { name, value } => node(
+                "Assign",
+                [("name", name.to_json()), ("value", child(value))],
+            ),
+            ExprKind::Call {
+                callee,
+                paren,
+                arguments,
+            } => node(
+                "Call",
+                [
+                    ("callee", child(callee)),
+                    ("paren", paren.to_json()),
+                    ("arguments", self.children_to_json(arguments)),
+                ],
+            ),
+            ExprKind::Get { object, name } => {
+                node("Get", [("object", child(object)), ("name", name.to_json())])
+            }
+            ExprKind::Set {
+                object,
+                name,
+                value,
+            } => node(
+                "Set",
+                [
+                    ("object", child(object)),
+                    ("name", name.to_json()),
+                    ("value", child(value)),
+                ],
+            ),
+            ExprKind::This { keyword } => node("This", [("keyword", keyword.to_json())]),
+            ExprKind::Super { keyword, method } => node(
+                "Super",
+                [("keyword", keyword.to_json()), ("method", method.to_json())],
+            ),
+        };
+        with_position(json, expr.id, expr.span)
+    }
+
+    fn function_to_json(&self, function: &FunctionDecl) -> Json {
+        node(
+            "Function",
+            [
+                ("name", function.name.to_json()),
+                ("params", list(&function.params, Token::to_json)),
+                ("body", self.children_to_json(&function.body)),
+            ],
+        )
+    }
+
+    fn stmt_to_json(&self, stmt: &Stmt) -> Json {
+        let child = |id: &NodeId| self.to_json(*id);
+        let json = match &stmt.kind {
+            StmtKind::Expression { expr } => node("Expression", [("expr", child(expr))]),
+            StmtKind::Print { expr } => node("Print", [("expr", child(expr))]),
+            StmtKind::Var { name, initializer } => node(
+                "Var",
+                [
+                    ("name", name.to_json()),
+                    ("initializer", optional(initializer.as_ref(), child)),
+                ],
+            ),
+            StmtKind::Block { statements } => {
+                node("Block", [("statements", self.children_to_json(statements))])
+            }
+            StmtKind::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => node(
+                "If",
+                [
+                    ("condition", child(condition)),
+                    ("then_branch", child(then_branch)),
+                    ("else_branch", optional(else_branch.as_ref(), child)),
+                ],
+            ),
+            StmtKind::While { condition, body } => node(
+                "While",
+                [("condition", child(condition)), ("body", child(body))],
+            ),
+            StmtKind::Function(function) => self.function_to_json(function),
+            StmtKind::Return { keyword, value } => node(
+                "Return",
+                [
+                    ("keyword", keyword.to_json()),
+                    ("value", optional(value.as_ref(), child)),
+                ],
+            ),
+            StmtKind::Class {
+                name,
+                superclass,
+                methods,
+            } => node(
+                "Class",
+                [
+                    ("name", name.to_json()),
+                    ("superclass", optional(superclass.as_ref(), child)),
+                    (
+                        "methods",
+                        list(methods, |method| self.function_to_json(method)),
+                    ),
+                ],
+            ),
+        };
+        with_position(json, stmt.id, stmt.span)
+    }
+}
+
+// Reads a program written with `parse --program --format=json` back into a
+// tree, e.g. to load a test fixture. Every node goes back under its id.
+pub fn program_from_json(text: &str) -> Result<Ast, String> {
+    let json: Json = text.parse()?;
+    let statements = json
+        .as_array()
+        .ok_or(String::from("a program must be an array of statements"))?;
+    program_from_documents(statements)
+}
+
+// Reads a program written with `parse --program --format=sexpr`, one
+// statement after another.
+pub fn program_from_sexpr(text: &str) -> Result<Ast, String> {
+    program_from_documents(&sexpr::parse_all(text)?)
+}
+
+// Reads an expression written with `parse --format=json`, without
+// `--program`.
+pub fn expression_from_json(text: &str) -> Result<Ast, String> {
+    expression_from_document(&text.parse()?)
+}
+
+// Reads an expression written with `parse --format=sexpr`.
+pub fn expression_from_sexpr(text: &str) -> Result<Ast, String> {
+    match sexpr::parse_all(text)?.as_slice() {
+        [expr] => expression_from_document(expr),
+        _ => Err(String::from("expected exactly one expression")),
+    }
+}
+
+fn expression_from_document(expr: &Json) -> Result<Ast, String> {
+    let mut reader = Reader::default();
+    let root = reader.expr(expr)?;
+    reader.finish(vec![root])
+}
+
+fn program_from_documents(statements: &[Json]) -> Result<Ast, String> {
+    let mut reader = Reader::default();
+    let roots = statements
+        .iter()
+        .map(|stmt| reader.stmt(stmt))
+        .collect::<Result<Vec<NodeId>, String>>()?;
+    reader.finish(roots)
+}
+
+// Collects the nodes of a tree read from JSON, to be put in id order once
+// they have all been read.
+#[derive(Default)]
+struct Reader {
+    nodes: Vec<AstNode>,
+}
+
+impl Reader {
+    fn place(&mut self, node: AstNode) -> NodeId {
+        let id = node.id();
+        self.nodes.push(node);
+        id
+    }
+
+    // A tree is complete when its ids are exactly the numbers below its
+    // number of nodes: with all of them in range, a missing id would mean
+    // another is used twice.
+    fn finish(mut self, roots: Vec<NodeId>) -> Result<Ast, String> {
+        let count = self.nodes.len();
+        if let Some(node) = self.nodes.iter().find(|node| node.id().index() >= count) {
+            return Err(format!(
+                "id {} is out of range for a tree of {} nodes",
+                node.id().0,
+                count
+            ));
+        }
+        self.nodes.sort_by_key(AstNode::id);
+        if let Some(pair) = self
+            .nodes
+            .windows(2)
+            .find(|pair| pair[0].id() == pair[1].id())
+        {
+            return Err(format!("two nodes have the id {}", pair[0].id().0));
+        }
+        Ok(Ast {
+            nodes: self.nodes,
+            roots,
+        })
+    }
+
+    fn child(&mut self, node: &Node, name: &str) -> Result<NodeId, String> {
+        self.expr(node.field(name)?)
+    }
+
+    fn optional(
+        &mut self,
+        node: &Node,
+        name: &str,
+        read: fn(&mut Self, &Json) -> Result<NodeId, String>,
+    ) -> Result<Option<NodeId>, String> {
+        match node.field(name)? {
+            Json::Null => Ok(None),
+            json => read(self, json).map(Some),
+        }
+    }
+
+    fn list<T>(
+        &mut self,
+        node: &Node,
+        name: &str,
+        read: fn(&mut Self, &Json) -> Result<T, String>,
+    ) -> Result<Vec<T>, String> {
+        node.array(name)?
+            .iter()
+            .map(|json| read(self, json))
+            .collect()
+    }
+
+    fn expr(&mut self, json: &Json) -> Result<NodeId, String> {
+        let node = Node::new(json)?;
+        let kind = match node.kind {
+            "Literal" => ExprKind::Literal {
+                value: LiteralValue::from_json(node.field("value")?)?,
+            },
+            "Grouping" => ExprKind::Grouping {
+                expr: self.child(&node, "expr")?,
+            },
+            "Unary" => ExprKind::Unary {
+                operator: node.token("operator")?,
+                right: self.child(&node, "right")?,
+            },
+            "Binary" => ExprKind::Binary {
+                left: self.child(&node, "left")?,
+                operator: node.token("operator")?,
+                right: self.child(&node, "right")?,
+            },
+            "Logical" => ExprKind::Logical {
+                left: self.child(&node, "left")?,
+                operator: node.token("operator")?,
+                right: self.child(&node, "right")?,
+            },
+            "Conditional" => ExprKind::Conditional {
+                condition: self.child(&node, "condition")?,
+                then_branch: self.child(&node, "then_branch")?,
+                else_branch: self.child(&node, "else_branch")?,
+            },
+            "Variable" => ExprKind::Variable {
+                name: node.token("name")?,
+            },
+            "Assign" => ExprKind::Assign {
+                name: node.token("name")?,
+                value: self.child(&node, "value")?,
+            },
+            "Call" => ExprKind::Call {
+                callee: self.child(&node, "callee")?,
+                paren: node.token("paren")?,
+                arguments: self.list(&node, "arguments", Reader::expr)?,
+            },
+            "Get" => ExprKind::Get {
+                object: self.child(&node, "object")?,
+                name: node.token("name")?,
+            },
+            "Set" => ExprKind::Set {
+                object: self.child(&node, "object")?,
+                name: node.token("name")?,
+                value: self.child(&node, "value")?,
+            },
+            "This" => ExprKind::This {
+                keyword: node.token("keyword")?,
+            },
+            "Super" => ExprKind::Super {
+                keyword: node.token("keyword")?,
+                method: node.token("method")?,
+            },
+            other => return Err(format!("unknown expression kind `{}`", other)),
+        };
+        Ok(self.place(AstNode::Expr(Expr {
+            id: node.id()?,
+            span: node.span()?,
+            kind,
+        })))
+    }
+
+    fn function(&mut self, json: &Json) -> Result<Rc<FunctionDecl>, String> {
+        let node = Node::new(json)?;
+        if node.kind != "Function" {
+            return Err(format!("expected a Function, found `{}`", node.kind));
+        }
+        Ok(Rc::new(FunctionDecl {
+            name: node.token("name")?,
+            params: node
+                .array("params")?
+                .iter()
+                .map(Token::from_json)
+                .collect::<Result<Vec<Token>, String>>()?,
+            body: self.list(&node, "body", Reader::stmt)?,
+        }))
+    }
+
+    fn stmt(&mut self, json: &Json) -> Result<NodeId, String> {
+        let node = Node::new(json)?;
+        let kind = match node.kind {
+            "Expression" => StmtKind::Expression {
+                expr: self.child(&node, "expr")?,
+            },
+            "Print" => StmtKind::Print {
+                expr: self.child(&node, "expr")?,
+            },
+            "Var" => StmtKind::Var {
+                name: node.token("name")?,
+                initializer: self.optional(&node, "initializer", Reader::expr)?,
+            },
+            "Block" => StmtKind::Block {
+                statements: self.list(&node, "statements", Reader::stmt)?,
+            },
+            "If" => StmtKind::If {
+                condition: self.child(&node, "condition")?,
+                then_branch: self.stmt(node.field("then_branch")?)?,
+                else_branch: self.optional(&node, "else_branch", Reader::stmt)?,
+            },
+            "While" => StmtKind::While {
+                condition: self.child(&node, "condition")?,
+                body: self.stmt(node.field("body")?)?,
+            },
+            "Function" => StmtKind::Function(self.function(json)?),
+            "Return" => StmtKind::Return {
+                keyword: node.token("keyword")?,
+                value: self.optional(&node, "value", Reader::expr)?,
+            },
+            "Class" => StmtKind::Class {
+                name: node.token("name")?,
+                superclass: self.optional(&node, "superclass", Reader::expr)?,
+                methods: self.list(&node, "methods", Reader::function)?,
+            },
+            other => return Err(format!("unknown statement kind `{}`", other)),
+        };
+        Ok(self.place(AstNode::Stmt(Stmt {
+            id: node.id()?,
+            span: node.span()?,
+            kind,
+        })))
+    }
+}
+
+// `n` when it is a non-negative integer that fits in a `u32`.
+fn whole_number(n: f64) -> Option<u32> {
+    (n.fract() == 0.0 && (0.0..=u32::MAX as f64).contains(&n)).then_some(n as u32)
+}
+
+struct Node<'a> {
+    json: &'a Json,
+    kind: &'a str,
+}
+
+impl<'a> Node<'a> {
+    fn new(json: &'a Json) -> Result<Self, String> {
+        let kind = json
+            .get("kind")
+            .and_then(Json::as_str)
+            .ok_or_else(|| format!("not a syntax tree node: {}", json))?;
+        Ok(Node { json, kind })
+    }
+
+    fn field(&self, name: &str) -> Result<&'a Json, String> {
+        self.json
+            .get(name)
+            .ok_or_else(|| format!("{} node is missing `{}`", self.kind, name))
+    }
+
+    fn array(&self, name: &str) -> Result<&'a [Json], String> {
+        self.field(name)?
+            .as_array()
+            .ok_or_else(|| format!("`{}` of a {} node must be an array", name, self.kind))
+    }
+
+    fn token(&self, name: &str) -> Result<Token, String> {
+        Token::from_json(self.field(name)?)
+    }
+
+    fn id(&self) -> Result<NodeId, String> {
+        match self.field("id")? {
+            Json::Number(id) => whole_number(*id).map(NodeId),
+            _ => None,
+        }
+        .ok_or_else(|| format!("id of a {} node must be a non-negative integer", self.kind))
+    }
+
+    fn span(&self) -> Result<Span, String> {
+        let offsets = match self.field("span")?.as_array() {
+            Some([Json::Number(start), Json::Number(end)]) => {
+                whole_number(*start).zip(whole_number(*end))
+            }
+            _ => None,
+        };
+        match offsets {
+            Some((start, end)) => Ok(Span::new(start as usize, end as usize)),
+            None => Err(format!(
+                "span of a {} node must be a [start, end] pair",
+                self.kind
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{parse_expression, parse_program};
+
+    #[test]
+    fn test_expression_json() {
+        let ast = parse_expression("-a.b").ast.unwrap();
+        let json = ast.to_json(ast.root());
+        assert_eq!(Some("Unary"), json.get("kind").and_then(Json::as_str));
+        assert_eq!("[0,4]", json.get("span").unwrap().to_string());
+        let object = json.get("right").and_then(|get| get.get("object")).unwrap();
+        assert_eq!(
+            r#"{"kind":"Variable","id":0,"span":[1,2],"name":{"kind":"IDENTIFIER","lexeme":"a","literal":null,"line":1,"column":2,"span":[1,2]}}"#,
+            object.to_string()
+        );
+    }
+
+    #[test]
+    fn test_literal_values() {
+        let ast = parse_expression("(\"hi\" == 2.5) == nil").ast.unwrap();
+        let json = ast.to_json(ast.root());
+        let grouping = json.get("left").and_then(|g| g.get("expr")).unwrap();
+        assert_eq!(
+            Some(&Json::string("hi")),
+            grouping.get("left").and_then(|l| l.get("value"))
+        );
+        assert_eq!(
+            Some(&Json::Number(2.5)),
+            grouping.get("right").and_then(|l| l.get("value"))
+        );
+        assert_eq!(
+            Some(&Json::Null),
+            json.get("right").and_then(|l| l.get("value"))
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let ast = parse_program(
+            "var a = 1; var b;
+             fun f(x, y) { if (x and !y) return \"s\"; else { while (true) a = a + 1; } return; }
+             class C < D { init() { this.x = super.m(f(1, 2), nil); } }",
+        )
+        .ast
+        .unwrap();
+        let roots = ast.roots().iter().map(|id| ast.to_json(*id)).collect();
+        let text = Json::Array(roots).pretty();
+        assert_eq!(Ok(ast), program_from_json(&text));
+    }
+
+    #[test]
+    fn test_expression_round_trip() {
+        let ast = parse_expression("a = f(1, -b.c) or \"s\" == nil")
+            .ast
+            .unwrap();
+        let json = ast.to_json(ast.root()).to_string();
+        assert_eq!(Ok(ast.clone()), expression_from_json(&json));
+        assert_eq!(
+            Ok(ast.clone()),
+            expression_from_sexpr(&ast.to_sexpr(ast.root()))
+        );
+        assert_eq!(
+            Err(String::from("expected exactly one expression")),
+            expression_from_sexpr("")
+        );
+    }
+
+    #[test]
+    fn test_expression_sexpr() {
+        let ast = parse_expression("\"hi\" == nil").ast.unwrap();
+        assert_eq!(
+            r#"(Literal :id 0 :span [0 4] :value "hi")"#,
+            ast.to_sexpr(NodeId(0))
+        );
+        assert_eq!(
+            r#"(Binary :id 2 :span [0 11]
+  :left (Literal :id 0 :span [0 4] :value "hi")
+  :operator (EQUAL_EQUAL :lexeme "==" :literal nil :line 1 :column 6 :span [5 7])
+  :right (Literal :id 1 :span [8 11] :value nil))"#,
+            ast.to_sexpr(ast.root())
+        );
+    }
+
+    #[test]
+    fn test_sexpr_round_trip() {
+        let ast = parse_program(
+            "var a = 1.5; print \"s\" + a;
+             fun f(x) { if (!x) return true; else { while (false) x = x - 1; } return; }
+             class C < D { init() { this.x = super.m(f(1), nil); } }",
+        )
+        .ast
+        .unwrap();
+        let text = ast
+            .roots()
+            .iter()
+            .map(|id| ast.to_sexpr(*id))
+            .collect::<Vec<String>>()
+            .join("\n");
+        assert_eq!(Ok(ast), program_from_sexpr(&text));
+    }
+
+    #[test]
+    fn test_bad_fixtures() {
+        assert_eq!(
+            Err(String::from("unknown statement kind `Loop`")),
+            program_from_json(r#"[{"kind": "Loop"}]"#)
+        );
+        assert_eq!(
+            Err(String::from("Print node is missing `expr`")),
+            program_from_json(r#"[{"kind": "Print"}]"#)
+        );
+        assert!(program_from_json(r#"{"kind": "Print"}"#).is_err());
+        assert_eq!(
+            Err(String::from("Print node is missing `expr`")),
+            program_from_sexpr("(Print :id 0 :span [0 1])")
+        );
+        assert_eq!(
+            Err(String::from("id 2 is out of range for a tree of 2 nodes")),
+            program_from_sexpr(
+                "(Expression :id 1 :span [0 1] :expr (This :id 2 :span [0 1] :keyword (THIS :lexeme \"this\" :literal nil :line 1 :column 1 :span [0 4])))"
+            )
+        );
+    }
+
+    #[test]
+    fn test_bad_ids() {
+        let literal = |id: &str| format!("(Literal :id {} :span [0 1] :value nil)", id);
+        let message = "id of a Literal node must be a non-negative integer";
+        assert_eq!(
+            Err(String::from(message)),
+            expression_from_sexpr(&literal("-1"))
+        );
+        assert_eq!(
+            Err(String::from(message)),
+            expression_from_sexpr(&literal("0.5"))
+        );
+        assert_eq!(
+            Err(String::from(message)),
+            expression_from_sexpr(&literal("1e10"))
+        );
+        assert_eq!(
+            Err(String::from(
+                "id 4000000000 is out of range for a tree of 1 nodes"
+            )),
+            expression_from_sexpr(&literal("4000000000"))
+        );
+        assert_eq!(
+            Err(String::from("two nodes have the id 0")),
+            expression_from_sexpr(&format!(
+                "(Grouping :id 0 :span [0 3] :expr {})",
+                literal("0")
+            ))
+        );
+        assert_eq!(
+            Err(String::from("two nodes have the id 0")),
+            program_from_json(
+                r#"[{"kind": "Print", "id": 0, "span": [0, 1], "expr": {"kind": "Literal", "id": 2, "span": [0, 1], "value": null}},
+                    {"kind": "Print", "id": 3, "span": [0, 1], "expr": {"kind": "Literal", "id": 0, "span": [0, 1], "value": null}}]"#
+            )
+        );
+        assert!(expression_from_sexpr("(Literal :id 0 :span [-1 1] :value nil)").is_err());
+    }
+}
@@ -0,0 +1,503 @@
+use std::rc::Rc;
+
+use super::visitor::{ExprVisitor, StmtVisitor};
+use super::{Ast, ExprKind, FunctionDecl, LiteralValue, NodeId, StmtKind};
+use crate::parser::precedence::*;
+use crate::parser::{infix_binding, Associativity};
+use crate::token::Token;
+
+const INDENT: &str = "    ";
+
+// Prints a tree back out as Lox source that parses to the same tree.
+// Parentheses are written only where precedence requires them, so groupings
+// in the original source that were redundant are dropped.
+pub fn unparse_program(ast: &Ast) -> String {
+    let mut unparser = Unparser::new(ast);
+    for stmt in ast.roots() {
+        unparser.statement(*stmt);
+    }
+    unparser.out
+}
+
+// Prints the root of a parsed expression.
+pub fn unparse_expression(ast: &Ast) -> String {
+    Unparser::new(ast).operand(ast.root(), ASSIGNMENT)
+}
+
+struct Unparser<'a> {
+    ast: &'a Ast,
+    out: String,
+    indent: usize,
+    // written instead of the indentation by the next line, which then
+    // continues the previous one, as in `} else {`
+    pending: Option<String>,
+}
+
+// How tightly the expression holds together when printed without
+// parentheses around it.
+fn precedence_of(ast: &Ast, id: NodeId) -> u8 {
+    match &ast.expr(id).kind {
+        ExprKind::Grouping { expr } => precedence_of(ast, *expr),
+        ExprKind::Unary { .. } => UNARY,
+        ExprKind::Binary { operator, .. } | ExprKind::Logical { operator, .. } => {
+            infix_binding(&operator.token_type).map_or(CALL, |(precedence, _)| precedence)
+        }
+        ExprKind::Conditional { .. } => CONDITIONAL,
+        ExprKind::Assign { .. } | ExprKind::Set { .. } => ASSIGNMENT,
+        _ => CALL,
+    }
+}
+
+// A trailing `if` without `else` would take an `else` that follows it.
+fn ends_with_open_if(ast: &Ast, id: NodeId) -> bool {
+    match &ast.stmt(id).kind {
+        StmtKind::If {
+            else_branch: None, ..
+        } => true,
+        StmtKind::If {
+            else_branch: Some(body),
+            ..
+        }
+        | StmtKind::While { body, .. } => ends_with_open_if(ast, *body),
+        _ => false,
+    }
+}
+
+fn text(token: &Token) -> String {
+    // tokens built by hand may have no lexeme
+    token.token_type.to_str()
+}
+
+impl<'a> Unparser<'a> {
+    fn new(ast: &'a Ast) -> Self {
+        Unparser {
+            ast,
+            out: String::new(),
+            indent: 0,
+            pending: None,
+        }
+    }
+
+    // Prints `expr` where the surrounding syntax only accepts operators of
+    // at least `min_precedence`, parenthesizing it otherwise.
+    fn operand(&mut self, id: NodeId, min_precedence: u8) -> String {
+        let text = self.expression(id);
+        if precedence_of(self.ast, id) < min_precedence {
+            format!("({})", text)
+        } else {
+            text
+        }
+    }
+
+    fn line(&mut self, text: &str) {
+        match self.pending.take() {
+            Some(prefix) => self.out.push_str(&prefix),
+            None => self.out.push_str(&INDENT.repeat(self.indent)),
+        }
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    fn expression(&mut self, id: NodeId) -> String {
+        self.ast.expr(id).accept(self)
+    }
+
+    fn statement(&mut self, id: NodeId) {
+        self.ast.stmt(id).accept(self);
+    }
+
+    // Writes `header` followed by a statement that belongs to it, such as
+    // the body of a loop. Blocks open on the header's line.
+    fn nested(&mut self, header: &str, body: NodeId) {
+        match &self.ast.stmt(body).kind {
+            StmtKind::Block { statements } => self.block(header, statements),
+            _ => {
+                self.line(header.trim_end());
+                self.indent += 1;
+                self.statement(body);
+                self.indent -= 1;
+            }
+        }
+    }
+
+    fn block(&mut self, header: &str, statements: &[NodeId]) {
+        if statements.is_empty() {
+            self.line(&format!("{}{{}}", header));
+            return;
+        }
+        self.line(&format!("{}{{", header));
+        self.indent += 1;
+        for stmt in statements {
+            self.statement(*stmt);
+        }
+        self.indent -= 1;
+        self.line("}");
+    }
+
+    fn function(&mut self, prefix: &str, function: &FunctionDecl) {
+        let params: Vec<String> = function.params.iter().map(text).collect();
+        let header = format!("{}{}({}) ", prefix, text(&function.name), params.join(", "));
+        self.block(&header, &function.body);
+    }
+
+    fn binary(&mut self, left: NodeId, operator: &Token, right: NodeId) -> String {
+        let (precedence, associativity) =
+            infix_binding(&operator.token_type).unwrap_or((CALL, Associativity::Left));
+        let (min_left, min_right) = match associativity {
+            Associativity::Left => (precedence, precedence + 1),
+            Associativity::Right => (precedence + 1, precedence),
+        };
+        format!(
+            "{} {} {}",
+            self.operand(left, min_left),
+            text(operator),
+            self.operand(right, min_right)
+        )
+    }
+}
+
+impl ExprVisitor<String> for Unparser<'_> {
+    fn visit_literal(&mut self, _: NodeId, value: &LiteralValue) -> String {
+        match value {
+            LiteralValue::String(value) => format!("\"{}\"", value),
+            other => other.to_string(),
+        }
+    }
+
+    fn visit_grouping(&mut self, _: NodeId, expr: NodeId) -> String {
+        self.expression(expr)
+    }
+
+    fn visit_unary(&mut self, _: NodeId, operator: &Token, right: NodeId) -> String {
+        format!("{}{}", text(operator), self.operand(right, UNARY))
+    }
+
+    fn visit_binary(&mut self, _: NodeId, left: NodeId, operator: &Token, right: NodeId) -> String {
+        self.binary(left, operator, right)
+    }
+
+    fn visit_logical(
+        &mut self,
+        _: NodeId,
+        left: NodeId,
+        operator: &Token,
+        right: NodeId,
+    ) -> String {
+        self.binary(left, operator, right)
+    }
+
+    fn visit_conditional(
+        &mut self,
+        _: NodeId,
+        condition: NodeId,
+        then_branch: NodeId,
+        else_branch: NodeId,
+    ) -> String {
+        format!(
+            "{} ? {} : {}",
+            self.operand(condition, CONDITIONAL + 1),
+            self.operand(then_branch, ASSIGNMENT),
+            self.operand(else_branch, CONDITIONAL)
+        )
+    }
+
+    fn visit_variable(&mut self, _: NodeId, name: &Token) -> String {
+        text(name)
+    }
+
+    fn visit_assign(&mut self, _: NodeId, name: &Token, value: NodeId) -> String {
+        format!("{} = {}", text(name), self.operand(value, ASSIGNMENT))
+    }
+
+    fn visit_call(&mut self, _: NodeId, callee: NodeId, _: &Token, arguments: &[NodeId]) -> String {
+        let arguments: Vec<String> = arguments
+            .iter()
+            .map(|argument| self.operand(*argument, ASSIGNMENT))
+            .collect();
+        format!("{}({})", self.operand(callee, CALL), arguments.join(", "))
+    }
+
+    fn visit_get(&mut self, _: NodeId, object: NodeId, name: &Token) -> String {
+        format!("{}.{}", self.operand(object, CALL), text(name))
+    }
+
+    fn visit_set(&mut self, _: NodeId, object: NodeId, name: &Token, value: NodeId) -> String {
+        format!(
+            "{}.{} = {}",
+            self.operand(object, CALL),
+            text(name),
+            self.operand(value, ASSIGNMENT)
+        )
+    }
+
+    fn visit_this(&mut self, _: NodeId, _: &Token) -> String {
+        String::from("this")
+    }
+
+    fn visit_super(&mut self, _: NodeId, _: &Token, method: &Token) -> String {
+        format!("super.{}", text(method))
+    }
+}
+
+impl StmtVisitor<()> for Unparser<'_> {
+    fn visit_expression_stmt(&mut self, expr: NodeId) {
+        let expr = self.operand(expr, ASSIGNMENT);
+        self.line(&format!("{};", expr));
+    }
+
+    fn visit_print(&mut self, expr: NodeId) {
+        let expr = self.operand(expr, ASSIGNMENT);
+        self.line(&format!("print {};", expr));
+    }
+
+    fn visit_var(&mut self, name: &Token, initializer: Option<NodeId>) {
+        let line = match initializer {
+            Some(initializer) => {
+                let initializer = self.operand(initializer, ASSIGNMENT);
+                format!("var {} = {};", text(name), initializer)
+            }
+            None => format!("var {};", text(name)),
+        };
+        self.line(&line);
+    }
+
+    fn visit_block(&mut self, statements: &[NodeId]) {
+        self.block("", statements);
+    }
+
+    fn visit_if(&mut self, condition: NodeId, then_branch: NodeId, else_branch: Option<NodeId>) {
+        let header = format!("if ({}) ", self.operand(condition, ASSIGNMENT));
+        let Some(else_branch) = else_branch else {
+            self.nested(&header, then_branch);
+            return;
+        };
+        // braces keep the `else` from attaching to an inner `if`
+        let braced = if ends_with_open_if(self.ast, then_branch) {
+            self.block(&header, &[then_branch]);
+            true
+        } else {
+            self.nested(&header, then_branch);
+            matches!(self.ast.stmt(then_branch).kind, StmtKind::Block { .. })
+        };
+        // `} else` only straight after the then branch's own closing brace
+        let prefix = if braced {
+            self.out.pop();
+            String::from(" ")
+        } else {
+            INDENT.repeat(self.indent)
+        };
+        if let StmtKind::If { .. } = self.ast.stmt(else_branch).kind {
+            self.pending = Some(prefix + "else ");
+            self.statement(else_branch);
+        } else {
+            self.pending = Some(prefix);
+            self.nested("else ", else_branch);
+        }
+    }
+
+    fn visit_while(&mut self, condition: NodeId, body: NodeId) {
+        let header = format!("while ({}) ", self.operand(condition, ASSIGNMENT));
+        self.nested(&header, body);
+    }
+
+    fn visit_function(&mut self, function: &Rc<FunctionDecl>) {
+        self.function("fun ", function);
+    }
+
+    fn visit_return(&mut self, _: &Token, value: Option<NodeId>) {
+        let line = match value {
+            Some(value) => format!("return {};", self.operand(value, ASSIGNMENT)),
+            None => String::from("return;"),
+        };
+        self.line(&line);
+    }
+
+    fn visit_class(
+        &mut self,
+        name: &Token,
+        superclass: Option<NodeId>,
+        methods: &[Rc<FunctionDecl>],
+    ) {
+        let mut header = format!("class {} ", text(name));
+        if let Some(superclass) = superclass {
+            header = format!("class {} < {} ", text(name), self.expression(superclass));
+        }
+        if methods.is_empty() {
+            self.line(&format!("{}{{}}", header));
+            return;
+        }
+        self.line(&format!("{}{{", header));
+        self.indent += 1;
+        for method in methods {
+            self.function("", method);
+        }
+        self.indent -= 1;
+        self.line("}");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ast::visitor::{walk_expr_mut, walk_stmts_mut, WalkerMut};
+    use crate::token::{Dialect, TokenType};
+    use crate::{parse_expression, parse_program, parse_program_with, Options};
+
+    // Parenthesized source has grouping nodes the printed form may not need.
+    struct Ungroup;
+
+    impl WalkerMut for Ungroup {
+        fn visit_expr(&mut self, ast: &mut Ast, id: NodeId) {
+            if let ExprKind::Grouping { expr: inner } = ast.expr(id).kind {
+                ast.expr_mut(id).kind = ast.expr(inner).kind.clone();
+                self.visit_expr(ast, id);
+            } else {
+                walk_expr_mut(self, ast, id);
+            }
+        }
+    }
+
+    fn sexprs(mut ast: Ast) -> Vec<String> {
+        let roots = ast.roots().to_vec();
+        walk_stmts_mut(&mut Ungroup, &mut ast, &roots);
+        roots
+            .iter()
+            .map(|id| ast.display(*id).to_string())
+            .collect()
+    }
+
+    fn round_trip(source_code: &str, options: &Options) -> String {
+        let ast = parse_program_with(source_code, options).ast.unwrap();
+        let printed = unparse_program(&ast);
+        let reparsed = parse_program_with(&printed, options).ast.unwrap();
+        assert_eq!(sexprs(ast), sexprs(reparsed), "{}", printed);
+        printed
+    }
+
+    fn expression(source_code: &str) -> String {
+        unparse_expression(&parse_expression(source_code).ast.unwrap())
+    }
+
+    #[test]
+    fn test_minimal_parentheses() {
+        assert_eq!("1.0 + 2.0 * 3.0", expression("(1 + (2 * 3))"));
+        assert_eq!("(1.0 + 2.0) * 3.0", expression("(1 + 2) * 3"));
+        assert_eq!("1.0 - (2.0 - 3.0)", expression("1 - (2 - 3)"));
+        assert_eq!("1.0 - 2.0 - 3.0", expression("(1 - 2) - 3"));
+        assert_eq!("-(a + b)", expression("-(a + b)"));
+        assert_eq!("!!a", expression("!(!a)"));
+        assert_eq!("(a or b) and c", expression("(a or b) and c"));
+        assert_eq!("a = b = c", expression("a = (b = c)"));
+        assert_eq!(
+            r#"(a + b).c(d = 1.0, "s")"#,
+            expression(r#"(a + b).c(d = 1, "s")"#)
+        );
+    }
+
+    #[test]
+    fn test_extended_operators() {
+        let options = Options {
+            dialect: Dialect::Extended,
+            ..Options::default()
+        };
+        assert_eq!(
+            "print 2.0 ** 3.0 ** 2.0;\nprint (2.0 ** 3.0) ** 2.0;\nprint (-2.0) ** 2.0;\n",
+            round_trip(
+                "print 2 ** (3 ** 2); print (2 ** 3) ** 2; print (-2) ** 2;",
+                &options
+            )
+        );
+        assert_eq!(
+            "x = (a ? b : c) ? d = 1.0 : e ? f : g % 2.0;\n",
+            round_trip("x = ((a ? b : c) ? (d = 1) : (e ? f : g % 2));", &options)
+        );
+    }
+
+    #[test]
+    fn test_program() {
+        let printed = round_trip(
+            "var a = 1; var b;
+             fun f(x, y) { if (x and !y) return \"s\"; else { while (true) a = a + 1; } return; }
+             class C < D { init() { this.x = super.m(f(1, 2), nil); } }
+             class E {}
+             for (var i = 0; i < 3; i = i + 1) print i;
+             if (a) print 1; else if (b) print 2; else {}",
+            &Options::default(),
+        );
+        assert_eq!(
+            "var a = 1.0;
+var b;
+fun f(x, y) {
+    if (x and !y)
+        return \"s\";
+    else {
+        while (true)
+            a = a + 1.0;
+    }
+    return;
+}
+class C < D {
+    init() {
+        this.x = super.m(f(1.0, 2.0), nil);
+    }
+}
+class E {}
+{
+    var i = 0.0;
+    while (i < 3.0) {
+        print i;
+        i = i + 1.0;
+    }
+}
+if (a)
+    print 1.0;
+else if (b)
+    print 2.0;
+else {}
+",
+            printed
+        );
+    }
+
+    #[test]
+    fn test_dangling_else() {
+        let printed = round_trip(
+            "if (a) { if (b) print 1; } else print 2;",
+            &Options::default(),
+        );
+        assert_eq!(
+            "if (a) {\n    if (b)\n        print 1.0;\n} else\n    print 2.0;\n",
+            printed
+        );
+
+        let printed = round_trip(
+            "if (a) while (b) { print 1; } else print 2;",
+            &Options::default(),
+        );
+        assert_eq!(
+            "if (a)\n    while (b) {\n        print 1.0;\n    }\nelse\n    print 2.0;\n",
+            printed
+        );
+
+        let printed = round_trip(
+            "if (a) { if (b) { print 1; } else { print 2; } } else { print 3; }",
+            &Options::default(),
+        );
+        assert_eq!(
+            "if (a) {\n    if (b) {\n        print 1.0;\n    } else {\n        print 2.0;\n    }\n} else {\n    print 3.0;\n}\n",
+            printed
+        );
+    }
+
+    #[test]
+    fn test_hand_built_tokens() {
+        // generated code has tokens without lexemes
+        let mut ast = parse_program("print a - b;").ast.unwrap();
+        if let StmtKind::Print { expr } = ast.stmt(ast.roots()[0]).kind {
+            if let ExprKind::Binary { operator, .. } = &mut ast.expr_mut(expr).kind {
+                *operator = Token::new(TokenType::Star, 1);
+            }
+        }
+        assert_eq!("print a * b;\n", unparse_program(&ast));
+    }
+}
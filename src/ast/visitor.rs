@@ -0,0 +1,544 @@
+use std::rc::Rc;
+
+use super::{Ast, Expr, ExprKind, FunctionDecl, LiteralValue, NodeId, Stmt, StmtKind};
+use crate::token::Token;
+
+// One method per kind of expression; `Expr::accept` dispatches to them.
+// Children are passed by id, for the visitor to look up in its tree.
+pub trait ExprVisitor<R> {
+    fn visit_literal(&mut self, id: NodeId, value: &LiteralValue) -> R;
+    fn visit_grouping(&mut self, id: NodeId, expr: NodeId) -> R;
+    fn visit_unary(&mut self, id: NodeId, operator: &Token, right: NodeId) -> R;
+    fn visit_binary(&mut self, id: NodeId, left: NodeId, operator: &Token, right: NodeId) -> R;
+    fn visit_logical(&mut self, id: NodeId, left: NodeId, operator: &Token, right: NodeId) -> R;
+    fn visit_conditional(
+        &mut self,
+        id: NodeId,
+        condition: NodeId,
+        then_branch: NodeId,
+        else_branch: NodeId,
+    ) -> R;
+    fn visit_variable(&mut self, id: NodeId, name: &Token) -> R;
+    fn visit_assign(&mut self, id: NodeId, name: &Token, value: NodeId) -> R;
+    fn visit_call(&mut self, id: NodeId, callee: NodeId, paren: &Token, arguments: &[NodeId]) -> R;
+    fn visit_get(&mut self, id: NodeId, object: NodeId, name: &Token) -> R;
+    fn visit_set(&mut self, id: NodeId, object: NodeId, name: &Token, value: NodeId) -> R;
+    fn visit_this(&mut self, id: NodeId, keyword: &Token) -> R;
+    fn visit_super(&mut self, id: NodeId, keyword: &Token, method: &Token) -> R;
+}
+
+// One method per kind of statement; `Stmt::accept` dispatches to them.
+pub trait StmtVisitor<R> {
+    fn visit_expression_stmt(&mut self, expr: NodeId) -> R;
+    fn visit_print(&mut self, expr: NodeId) -> R;
+    fn visit_var(&mut self, name: &Token, initializer: Option<NodeId>) -> R;
+    fn visit_block(&mut self, statements: &[NodeId]) -> R;
+    fn visit_if(
+        &mut self,
+        condition: NodeId,
+        then_branch: NodeId,
+        else_branch: Option<NodeId>,
+    ) -> R;
+    fn visit_while(&mut self, condition: NodeId, body: NodeId) -> R;
+    fn visit_function(&mut self, function: &Rc<FunctionDecl>) -> R;
+    fn visit_return(&mut self, keyword: &Token, value: Option<NodeId>) -> R;
+    fn visit_class(
+        &mut self,
+        name: &Token,
+        superclass: Option<NodeId>,
+        methods: &[Rc<FunctionDecl>],
+    ) -> R;
+}
+
+// Like `ExprVisitor`, for passes that rewrite nodes in place.
+pub trait ExprVisitorMut<R> {
+    fn visit_literal(&mut self, id: NodeId, value: &mut LiteralValue) -> R;
+    fn visit_grouping(&mut self, id: NodeId, expr: &mut NodeId) -> R;
+    fn visit_unary(&mut self, id: NodeId, operator: &mut Token, right: &mut NodeId) -> R;
+    fn visit_binary(
+        &mut self,
+        id: NodeId,
+        left: &mut NodeId,
+        operator: &mut Token,
+        right: &mut NodeId,
+    ) -> R;
+    fn visit_logical(
+        &mut self,
+        id: NodeId,
+        left: &mut NodeId,
+        operator: &mut Token,
+        right: &mut NodeId,
+    ) -> R;
+    fn visit_conditional(
+        &mut self,
+        id: NodeId,
+        condition: &mut NodeId,
+        then_branch: &mut NodeId,
+        else_branch: &mut NodeId,
+    ) -> R;
+    fn visit_variable(&mut self, id: NodeId, name: &mut Token) -> R;
+    fn visit_assign(&mut self, id: NodeId, name: &mut Token, value: &mut NodeId) -> R;
+    fn visit_call(
+        &mut self,
+        id: NodeId,
+        callee: &mut NodeId,
+        paren: &mut Token,
+        arguments: &mut Vec<NodeId>,
+    ) -> R;
+    fn visit_get(&mut self, id: NodeId, object: &mut NodeId, name: &mut Token) -> R;
+    fn visit_set(
+        &mut self,
+        id: NodeId,
+        object: &mut NodeId,
+        name: &mut Token,
+        value: &mut NodeId,
+    ) -> R;
+    fn visit_this(&mut self, id: NodeId, keyword: &mut Token) -> R;
+    fn visit_super(&mut self, id: NodeId, keyword: &mut Token, method: &mut Token) -> R;
+}
+
+// Like `StmtVisitor`, for passes that rewrite nodes in place.
+pub trait StmtVisitorMut<R> {
+    fn visit_expression_stmt(&mut self, expr: &mut NodeId) -> R;
+    fn visit_print(&mut self, expr: &mut NodeId) -> R;
+    fn visit_var(&mut self, name: &mut Token, initializer: &mut Option<NodeId>) -> R;
+    fn visit_block(&mut self, statements: &mut Vec<NodeId>) -> R;
+    fn visit_if(
+        &mut self,
+        condition: &mut NodeId,
+        then_branch: &mut NodeId,
+        else_branch: &mut Option<NodeId>,
+    ) -> R;
+    fn visit_while(&mut self, condition: &mut NodeId, body: &mut NodeId) -> R;
+    fn visit_function(&mut self, function: &mut FunctionDecl) -> R;
+    fn visit_return(&mut self, keyword: &mut Token, value: &mut Option<NodeId>) -> R;
+    fn visit_class(
+        &mut self,
+        name: &mut Token,
+        superclass: &mut Option<NodeId>,
+        methods: &mut [Rc<FunctionDecl>],
+    ) -> R;
+}
+
+impl Expr {
+    pub fn accept<R, V: ExprVisitor<R> + ?Sized>(&self, visitor: &mut V) -> R {
+        let id = self.id;
+        match &self.kind {
+            ExprKind::Literal { value } => visitor.visit_literal(id, value),
+            ExprKind::Grouping { expr } => visitor.visit_grouping(id, *expr),
+            ExprKind::Unary { operator, right } => visitor.visit_unary(id, operator, *right),
+            ExprKind::Binary {
+                left,
+                operator,
+                right,
+            } => visitor.visit_binary(id, *left, operator, *right),
+            ExprKind::Logical {
+                left,
+                operator,
+                right,
+            } => visitor.visit_logical(id, *left, operator, *right),
+            ExprKind::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+            } => visitor.visit_conditional(id, *condition, *then_branch, *else_branch),
+            ExprKind::Variable { name } => visitor.visit_variable(id, name),
+            ExprKind::Assign { name, value } => visitor.visit_assign(id, name, *value),
+            ExprKind::Call {
+                callee,
+                paren,
+                arguments,
+            } => visitor.visit_call(id, *callee, paren, arguments),
+            ExprKind::Get { object, name } => visitor.visit_get(id, *object, name),
+            ExprKind::Set {
+                object,
+                name,
+                value,
+            } => visitor.visit_set(id, *object, name, *value),
+            ExprKind::This { keyword } => visitor.visit_this(id, keyword),
+            ExprKind::Super { keyword, method } => visitor.visit_super(id, keyword, method),
+        }
+    }
+
+    pub fn accept_mut<R, V: ExprVisitorMut<R> + ?Sized>(&mut self, visitor: &mut V) -> R {
+        let id = self.id;
+        match &mut self.kind {
+            ExprKind::Literal { value } => visitor.visit_literal(id, value),
+            ExprKind::Grouping { expr } => visitor.visit_grouping(id, expr),
+            ExprKind::Unary { operator, right } => visitor.visit_unary(id, operator, right),
+            ExprKind::Binary {
+                left,
+                operator,
+                right,
+            } => visitor.visit_binary(id, left, operator, right),
+            ExprKind::Logical {
+                left,
+                operator,
+                right,
+            } => visitor.visit_logical(id, left, operator, right),
+            ExprKind::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+            } => visitor.visit_conditional(id, condition, then_branch, else_branch),
+            ExprKind::Variable { name } => visitor.visit_variable(id, name),
+            ExprKind::Assign { name, value } => visitor.visit_assign(id, name, value),
+            ExprKind::Call {
+                callee,
+                paren,
+                arguments,
+            } => visitor.visit_call(id, callee, paren, arguments),
+            ExprKind::Get { object, name } => visitor.visit_get(id, object, name),
+            ExprKind::Set {
+                object,
+                name,
+                value,
+            } => visitor.visit_set(id, object, name, value),
+            ExprKind::This { keyword } => visitor.visit_this(id, keyword),
+            ExprKind::Super { keyword, method } => visitor.visit_super(id, keyword, method),
+        }
+    }
+}
+
+impl Stmt {
+    pub fn accept<R, V: StmtVisitor<R> + ?Sized>(&self, visitor: &mut V) -> R {
+        match &self.kind {
+            StmtKind::Expression { expr } => visitor.visit_expression_stmt(*expr),
+            StmtKind::Print { expr } => visitor.visit_print(*expr),
+            StmtKind::Var { name, initializer } => visitor.visit_var(name, *initializer),
+            StmtKind::Block { statements } => visitor.visit_block(statements),
+            StmtKind::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => visitor.visit_if(*condition, *then_branch, *else_branch),
+            StmtKind::While { condition, body } => visitor.visit_while(*condition, *body),
+            StmtKind::Function(function) => visitor.visit_function(function),
+            StmtKind::Return { keyword, value } => visitor.visit_return(keyword, *value),
+            StmtKind::Class {
+                name,
+                superclass,
+                methods,
+            } => visitor.visit_class(name, *superclass, methods),
+        }
+    }
+
+    pub fn accept_mut<R, V: StmtVisitorMut<R> + ?Sized>(&mut self, visitor: &mut V) -> R {
+        match &mut self.kind {
+            StmtKind::Expression { expr } => visitor.visit_expression_stmt(expr),
+            StmtKind::Print { expr } => visitor.visit_print(expr),
+            StmtKind::Var { name, initializer } => visitor.visit_var(name, initializer),
+            StmtKind::Block { statements } => visitor.visit_block(statements),
+            StmtKind::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => visitor.visit_if(condition, then_branch, else_branch),
+            StmtKind::While { condition, body } => visitor.visit_while(condition, body),
+            StmtKind::Function(function) => visitor.visit_function(Rc::make_mut(function)),
+            StmtKind::Return { keyword, value } => visitor.visit_return(keyword, value),
+            StmtKind::Class {
+                name,
+                superclass,
+                methods,
+            } => visitor.visit_class(name, superclass, methods),
+        }
+    }
+}
+
+// The ids of a statement's direct children: expressions, then statements,
+// in source order. Function bodies count as children of the declaration.
+fn stmt_children(stmt: &Stmt) -> (Vec<NodeId>, Vec<NodeId>) {
+    match &stmt.kind {
+        StmtKind::Expression { expr } | StmtKind::Print { expr } => (vec![*expr], vec![]),
+        StmtKind::Var { initializer, .. } => (initializer.iter().copied().collect(), vec![]),
+        StmtKind::Block { statements } => (vec![], statements.clone()),
+        StmtKind::If {
+            condition,
+            then_branch,
+            else_branch,
+        } => {
+            let mut branches = vec![*then_branch];
+            branches.extend(else_branch);
+            (vec![*condition], branches)
+        }
+        StmtKind::While { condition, body } => (vec![*condition], vec![*body]),
+        StmtKind::Function(function) => (vec![], function.body.clone()),
+        StmtKind::Return { value, .. } => (value.iter().copied().collect(), vec![]),
+        StmtKind::Class {
+            superclass,
+            methods,
+            ..
+        } => (
+            superclass.iter().copied().collect(),
+            methods
+                .iter()
+                .flat_map(|method| method.body.clone())
+                .collect(),
+        ),
+    }
+}
+
+fn expr_children(expr: &Expr) -> Vec<NodeId> {
+    match &expr.kind {
+        ExprKind::Literal { .. }
+        | ExprKind::Variable { .. }
+        | ExprKind::This { .. }
+        | ExprKind::Super { .. } => vec![],
+        ExprKind::Grouping { expr, .. } => vec![*expr],
+        ExprKind::Unary { right, .. } => vec![*right],
+        ExprKind::Binary { left, right, .. } | ExprKind::Logical { left, right, .. } => {
+            vec![*left, *right]
+        }
+        ExprKind::Conditional {
+            condition,
+            then_branch,
+            else_branch,
+        } => vec![*condition, *then_branch, *else_branch],
+        ExprKind::Assign { value, .. } => vec![*value],
+        ExprKind::Call {
+            callee, arguments, ..
+        } => {
+            let mut children = vec![*callee];
+            children.extend(arguments);
+            children
+        }
+        ExprKind::Get { object, .. } => vec![*object],
+        ExprKind::Set { object, value, .. } => vec![*object, *value],
+    }
+}
+
+// Walks the whole tree by default. A pass overrides only the hooks it cares
+// about and calls the matching `walk_*` function to keep descending.
+pub trait Walker {
+    fn visit_stmt(&mut self, ast: &Ast, stmt: &Stmt) {
+        walk_stmt(self, ast, stmt);
+    }
+
+    fn visit_expr(&mut self, ast: &Ast, expr: &Expr) {
+        walk_expr(self, ast, expr);
+    }
+
+    fn visit_function_decl(&mut self, ast: &Ast, function: &FunctionDecl) {
+        walk_function_decl(self, ast, function);
+    }
+}
+
+pub fn walk_stmts<W: Walker + ?Sized>(walker: &mut W, ast: &Ast, statements: &[NodeId]) {
+    for id in statements {
+        walker.visit_stmt(ast, ast.stmt(*id));
+    }
+}
+
+pub fn walk_stmt<W: Walker + ?Sized>(walker: &mut W, ast: &Ast, stmt: &Stmt) {
+    match &stmt.kind {
+        StmtKind::Function(function) => walker.visit_function_decl(ast, function),
+        StmtKind::Class {
+            superclass,
+            methods,
+            ..
+        } => {
+            if let Some(superclass) = superclass {
+                walker.visit_expr(ast, ast.expr(*superclass));
+            }
+            for method in methods {
+                walker.visit_function_decl(ast, method);
+            }
+        }
+        _ => {
+            let (exprs, statements) = stmt_children(stmt);
+            for id in exprs {
+                walker.visit_expr(ast, ast.expr(id));
+            }
+            walk_stmts(walker, ast, &statements);
+        }
+    }
+}
+
+pub fn walk_function_decl<W: Walker + ?Sized>(walker: &mut W, ast: &Ast, function: &FunctionDecl) {
+    walk_stmts(walker, ast, &function.body);
+}
+
+pub fn walk_expr<W: Walker + ?Sized>(walker: &mut W, ast: &Ast, expr: &Expr) {
+    for id in expr_children(expr) {
+        walker.visit_expr(ast, ast.expr(id));
+    }
+}
+
+// `Walker` for passes that rewrite the tree in place. Nodes are passed by id
+// so that a pass can replace them while it holds the tree.
+pub trait WalkerMut {
+    fn visit_stmt(&mut self, ast: &mut Ast, id: NodeId) {
+        walk_stmt_mut(self, ast, id);
+    }
+
+    fn visit_expr(&mut self, ast: &mut Ast, id: NodeId) {
+        walk_expr_mut(self, ast, id);
+    }
+}
+
+pub fn walk_stmts_mut<W: WalkerMut + ?Sized>(walker: &mut W, ast: &mut Ast, statements: &[NodeId]) {
+    for id in statements {
+        walker.visit_stmt(ast, *id);
+    }
+}
+
+pub fn walk_stmt_mut<W: WalkerMut + ?Sized>(walker: &mut W, ast: &mut Ast, id: NodeId) {
+    let (exprs, statements) = stmt_children(ast.stmt(id));
+    for id in exprs {
+        walker.visit_expr(ast, id);
+    }
+    walk_stmts_mut(walker, ast, &statements);
+}
+
+pub fn walk_expr_mut<W: WalkerMut + ?Sized>(walker: &mut W, ast: &mut Ast, id: NodeId) {
+    for id in expr_children(ast.expr(id)) {
+        walker.visit_expr(ast, id);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{parse_expression, parse_program};
+
+    // records every variable read, in source order
+    struct Reads(Vec<String>);
+
+    impl Walker for Reads {
+        fn visit_expr(&mut self, ast: &Ast, expr: &Expr) {
+            if let ExprKind::Variable { name } = &expr.kind {
+                self.0.push(name.lexeme.clone());
+            }
+            walk_expr(self, ast, expr);
+        }
+    }
+
+    struct Uppercase;
+
+    impl WalkerMut for Uppercase {
+        fn visit_expr(&mut self, ast: &mut Ast, id: NodeId) {
+            if let ExprKind::Variable { name } | ExprKind::Assign { name, .. } =
+                &mut ast.expr_mut(id).kind
+            {
+                name.lexeme = name.lexeme.to_uppercase();
+            }
+            walk_expr_mut(self, ast, id);
+        }
+    }
+
+    // the depth of the deepest expression
+    struct Depth<'a>(&'a Ast);
+
+    impl Depth<'_> {
+        fn of(&mut self, id: NodeId) -> usize {
+            self.0.expr(id).accept(self)
+        }
+    }
+
+    impl ExprVisitor<usize> for Depth<'_> {
+        fn visit_literal(&mut self, _: NodeId, _: &LiteralValue) -> usize {
+            1
+        }
+        fn visit_grouping(&mut self, _: NodeId, expr: NodeId) -> usize {
+            1 + self.of(expr)
+        }
+        fn visit_unary(&mut self, _: NodeId, _: &Token, right: NodeId) -> usize {
+            1 + self.of(right)
+        }
+        fn visit_binary(&mut self, _: NodeId, left: NodeId, _: &Token, right: NodeId) -> usize {
+            1 + self.of(left).max(self.of(right))
+        }
+        fn visit_logical(&mut self, _: NodeId, left: NodeId, _: &Token, right: NodeId) -> usize {
+            1 + self.of(left).max(self.of(right))
+        }
+        fn visit_conditional(&mut self, _: NodeId, c: NodeId, t: NodeId, e: NodeId) -> usize {
+            1 + self.of(c).max(self.of(t)).max(self.of(e))
+        }
+        fn visit_variable(&mut self, _: NodeId, _: &Token) -> usize {
+            1
+        }
+        fn visit_assign(&mut self, _: NodeId, _: &Token, value: NodeId) -> usize {
+            1 + self.of(value)
+        }
+        fn visit_call(&mut self, _: NodeId, callee: NodeId, _: &Token, args: &[NodeId]) -> usize {
+            let deepest = args.iter().map(|argument| self.of(*argument)).max();
+            1 + self.of(callee).max(deepest.unwrap_or(0))
+        }
+        fn visit_get(&mut self, _: NodeId, object: NodeId, _: &Token) -> usize {
+            1 + self.of(object)
+        }
+        fn visit_set(&mut self, _: NodeId, object: NodeId, _: &Token, value: NodeId) -> usize {
+            1 + self.of(object).max(self.of(value))
+        }
+        fn visit_this(&mut self, _: NodeId, _: &Token) -> usize {
+            1
+        }
+        fn visit_super(&mut self, _: NodeId, _: &Token, _: &Token) -> usize {
+            1
+        }
+    }
+
+    // renames every variable read to `x` through the mutable visitor
+    struct RenameReads;
+
+    impl ExprVisitorMut<()> for RenameReads {
+        fn visit_literal(&mut self, _: NodeId, _: &mut LiteralValue) {}
+        fn visit_grouping(&mut self, _: NodeId, _: &mut NodeId) {}
+        fn visit_unary(&mut self, _: NodeId, _: &mut Token, _: &mut NodeId) {}
+        fn visit_binary(&mut self, _: NodeId, _: &mut NodeId, _: &mut Token, _: &mut NodeId) {}
+        fn visit_logical(&mut self, _: NodeId, _: &mut NodeId, _: &mut Token, _: &mut NodeId) {}
+        fn visit_conditional(&mut self, _: NodeId, _: &mut NodeId, _: &mut NodeId, _: &mut NodeId) {
+        }
+        fn visit_variable(&mut self, _: NodeId, name: &mut Token) {
+            name.lexeme = String::from("x");
+        }
+        fn visit_assign(&mut self, _: NodeId, _: &mut Token, _: &mut NodeId) {}
+        fn visit_call(&mut self, _: NodeId, _: &mut NodeId, _: &mut Token, _: &mut Vec<NodeId>) {}
+        fn visit_get(&mut self, _: NodeId, _: &mut NodeId, _: &mut Token) {}
+        fn visit_set(&mut self, _: NodeId, _: &mut NodeId, _: &mut Token, _: &mut NodeId) {}
+        fn visit_this(&mut self, _: NodeId, _: &mut Token) {}
+        fn visit_super(&mut self, _: NodeId, _: &mut Token, _: &mut Token) {}
+    }
+
+    #[test]
+    fn test_walker() {
+        let ast = parse_program(
+            "var a = b + 1;
+             fun f(x) { if (x) return a; else print c(d.e); }
+             class C < D { m() { this.f = g; } }",
+        )
+        .ast
+        .unwrap();
+        let mut reads = Reads(Vec::new());
+        walk_stmts(&mut reads, &ast, ast.roots());
+        assert_eq!(vec!["b", "x", "a", "c", "d", "D", "g"], reads.0);
+    }
+
+    #[test]
+    fn test_walker_mut() {
+        let mut ast = parse_program("a = b * (c - 1);").ast.unwrap();
+        let roots = ast.roots().to_vec();
+        walk_stmts_mut(&mut Uppercase, &mut ast, &roots);
+        assert_eq!(
+            "(; (= A (* B (group (- C 1.0)))))",
+            ast.display(roots[0]).to_string()
+        );
+    }
+
+    #[test]
+    fn test_expr_visitor() {
+        let ast = parse_expression("1 + (2 * -f(3))").ast.unwrap();
+        assert_eq!(6, Depth(&ast).of(ast.root()));
+    }
+
+    #[test]
+    fn test_expr_visitor_mut() {
+        let mut ast = parse_expression("a + b").ast.unwrap();
+        let ExprKind::Binary { left, .. } = ast.expr(ast.root()).kind else {
+            panic!("expected a binary expression");
+        };
+        ast.expr_mut(left).accept_mut(&mut RenameReads);
+        assert_eq!("(+ x b)", ast.display(ast.root()).to_string());
+    }
+}
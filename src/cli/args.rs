@@ -0,0 +1,249 @@
+use std::collections::{HashMap, HashSet};
+
+use super::CliError;
+use crate::errors::lint::LintConfig;
+use crate::errors::{ColorChoice, ErrorFormat};
+use crate::token::Dialect;
+
+// An option a command accepts, e.g. `--format <FORMAT>`. Switches have no
+// value placeholder.
+pub struct OptionSpec {
+    pub name: &'static str,
+    pub value: Option<&'static str>,
+    pub help: &'static str,
+}
+
+impl OptionSpec {
+    pub fn usage(&self) -> String {
+        match self.value {
+            Some(value) => format!("--{} <{}>", self.name, value),
+            None => format!("--{}", self.name),
+        }
+    }
+}
+
+pub const GLOBAL_OPTIONS: &[OptionSpec] = &[
+    OptionSpec {
+        name: "error-format",
+        value: Some("short|human"),
+        help: "how diagnostics are printed (default: short)",
+    },
+    OptionSpec {
+        name: "color",
+        value: Some("auto|always|never"),
+        help: "colorize human diagnostics (default: auto)",
+    },
+    OptionSpec {
+        name: "dialect",
+        value: Some("lox|extended"),
+        help: "language dialect; extended adds `%`, `**` and `?:` (default: lox)",
+    },
+    OptionSpec {
+        name: "max-errors",
+        value: Some("N"),
+        help: "show at most N diagnostics",
+    },
+    OptionSpec {
+        name: "help",
+        value: None,
+        help: "print help",
+    },
+    OptionSpec {
+        name: "version",
+        value: None,
+        help: "print version",
+    },
+];
+
+// Options understood by every command, wherever they appear on the line.
+#[derive(Debug, Default)]
+pub struct GlobalOptions {
+    pub error_format: ErrorFormat,
+    pub color: ColorChoice,
+    pub dialect: Dialect,
+    pub max_errors: Option<usize>,
+    pub lints: LintConfig,
+    pub eval: Option<String>,
+    pub help: bool,
+    pub version: bool,
+}
+
+impl GlobalOptions {
+    fn set(&mut self, name: &str, value: Option<String>) -> Result<(), CliError> {
+        let value = value.unwrap_or_default();
+        match name {
+            "error-format" => self.error_format = value.parse().map_err(CliError::Usage)?,
+            "color" => self.color = value.parse().map_err(CliError::Usage)?,
+            "dialect" => self.dialect = value.parse().map_err(CliError::Usage)?,
+            "max-errors" => {
+                let n = value
+                    .parse::<usize>()
+                    .map_err(|_| CliError::Usage(String::from("--max-errors expects a number")))?;
+                self.max_errors = Some(n);
+            }
+            "help" => self.help = true,
+            "version" => self.version = true,
+            _ => unreachable!("unhandled global option --{}", name),
+        }
+        Ok(())
+    }
+}
+
+// The options and operands given to a single command.
+#[derive(Debug, Default)]
+pub struct Matches {
+    values: HashMap<&'static str, String>,
+    switches: HashSet<&'static str>,
+    pub positionals: Vec<String>,
+}
+
+impl Matches {
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(String::as_str)
+    }
+
+    pub fn is_set(&self, name: &str) -> bool {
+        self.switches.contains(name)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ParsedArgs {
+    pub global: GlobalOptions,
+    pub command: Option<String>,
+    pub matches: Matches,
+}
+
+// Parses everything after the program name. `command_options` maps a command
+// name to the options it accepts, which are only recognised after it.
+pub fn parse_args<F>(args: &[String], command_options: F) -> Result<ParsedArgs, CliError>
+where
+    F: Fn(&str) -> Option<&'static [OptionSpec]>,
+{
+    let mut parsed = ParsedArgs::default();
+    let mut command_specs: &'static [OptionSpec] = &[];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" => parsed.global.help = true,
+            "-V" => parsed.global.version = true,
+            "-e" => {
+                let code = args
+                    .next()
+                    .ok_or_else(|| CliError::Usage(String::from("-e expects Lox code")))?;
+                parsed.global.eval = Some(code.clone());
+            }
+            "-W" | "-A" | "-D" => {
+                let name = args
+                    .next()
+                    .ok_or_else(|| CliError::Usage(format!("{} expects a lint name", arg)))?;
+                parsed
+                    .global
+                    .lints
+                    .apply_flag(arg, name)
+                    .map_err(CliError::Usage)?;
+            }
+            long if long.starts_with("--") => {
+                let (name, inline_value) = match long[2..].split_once('=') {
+                    Some((name, value)) => (name, Some(value.to_string())),
+                    None => (&long[2..], None),
+                };
+                let (spec, is_global) = match GLOBAL_OPTIONS.iter().find(|s| s.name == name) {
+                    Some(spec) => (spec, true),
+                    None => match command_specs.iter().find(|s| s.name == name) {
+                        Some(spec) => (spec, false),
+                        None => return Err(CliError::Usage(format!("unknown option: --{}", name))),
+                    },
+                };
+                let value =
+                    match (spec.value, inline_value) {
+                        (None, None) => None,
+                        (None, Some(_)) => {
+                            return Err(CliError::Usage(format!("--{} takes no value", name)))
+                        }
+                        (Some(_), Some(value)) => Some(value),
+                        (Some(_), None) => Some(args.next().cloned().ok_or_else(|| {
+                            CliError::Usage(format!("--{} expects a value", name))
+                        })?),
+                    };
+                if is_global {
+                    parsed.global.set(spec.name, value)?;
+                } else if let Some(value) = value {
+                    parsed.matches.values.insert(spec.name, value);
+                } else {
+                    parsed.matches.switches.insert(spec.name);
+                }
+            }
+            short if short.starts_with('-') && short != "-" => {
+                return Err(CliError::Usage(format!("unknown option: {}", short)))
+            }
+            operand => {
+                if parsed.command.is_none() {
+                    command_specs = command_options(operand)
+                        .ok_or_else(|| CliError::Usage(format!("Unknown command: {}", operand)))?;
+                    parsed.command = Some(operand.to_string());
+                } else {
+                    parsed.matches.positionals.push(operand.to_string());
+                }
+            }
+        }
+    }
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const FORMAT: &[OptionSpec] = &[OptionSpec {
+        name: "format",
+        value: Some("FORMAT"),
+        help: "",
+    }];
+
+    fn parse(args: &[&str]) -> Result<ParsedArgs, CliError> {
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        parse_args(&args, |command| match command {
+            "tokenize" => Some(FORMAT),
+            "explain" => Some(&[]),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn test_global_and_command_options() {
+        let parsed = parse(&[
+            "--error-format=human",
+            "tokenize",
+            "--format",
+            "json",
+            "--dialect",
+            "extended",
+            "-D",
+            "warnings",
+            "file.lox",
+        ])
+        .unwrap();
+        assert_eq!(ErrorFormat::Human, parsed.global.error_format);
+        assert_eq!(Dialect::Extended, parsed.global.dialect);
+        assert_eq!(Some("tokenize"), parsed.command.as_deref());
+        assert_eq!(Some("json"), parsed.matches.value("format"));
+        assert_eq!(vec!["file.lox"], parsed.matches.positionals);
+    }
+
+    #[test]
+    fn test_usage_errors() {
+        let usage_error = |args: &[&str]| parse(args).map(|_| ()).unwrap_err().exit_code();
+        assert_eq!(64, usage_error(&["frobnicate"]));
+        assert_eq!(64, usage_error(&["explain", "--format", "json"]));
+        assert_eq!(64, usage_error(&["--format=json", "tokenize"]));
+        assert_eq!(64, usage_error(&["--color=sometimes"]));
+        assert_eq!(64, usage_error(&["tokenize", "-e"]));
+    }
+
+    #[test]
+    fn test_stdin_operand() {
+        let parsed = parse(&["tokenize", "-"]).unwrap();
+        assert_eq!(vec!["-"], parsed.matches.positionals);
+    }
+}
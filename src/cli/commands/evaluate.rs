@@ -0,0 +1,40 @@
+use std::io::{self, Write};
+
+use super::{Command, Context, NO_TRACEBACK};
+use crate::cli::args::{Matches, OptionSpec};
+use crate::cli::CliError;
+use crate::interpreter::Interpreter;
+use crate::parse_expression_with;
+
+pub struct Evaluate;
+
+impl Command for Evaluate {
+    fn name(&self) -> &'static str {
+        "evaluate"
+    }
+
+    fn about(&self) -> &'static str {
+        "Evaluate a Lox expression and print its value"
+    }
+
+    fn options(&self) -> &'static [OptionSpec] {
+        &[NO_TRACEBACK]
+    }
+
+    fn run(&self, ctx: &Context, matches: &Matches) -> Result<(), CliError> {
+        let source = ctx.source(matches)?;
+        let result = parse_expression_with(&source.text, &ctx.options());
+        let mut error_handler = ctx.error_handler(&source);
+        error_handler.extend(result.diagnostics);
+        error_handler.display_errors(&source.text);
+        let ast = match result.ast {
+            Some(ast) if !error_handler.has_error() => ast,
+            _ => return Err(CliError::Compile),
+        };
+        let value = Interpreter::new()
+            .interpret_expression(ast)
+            .map_err(|unwind| ctx.runtime_error(&source, matches, unwind))?;
+        writeln!(io::stdout().lock(), "{}", value)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,42 @@
+use std::io::{self, Write};
+
+use super::{Command, Context};
+use crate::cli::args::Matches;
+use crate::cli::CliError;
+use crate::errors::ErrorCode;
+
+pub struct Explain;
+
+impl Command for Explain {
+    fn name(&self) -> &'static str {
+        "explain"
+    }
+
+    fn about(&self) -> &'static str {
+        "Explain an error code such as L0001"
+    }
+
+    fn operands(&self) -> &'static str {
+        "<code>"
+    }
+
+    fn run(&self, _ctx: &Context, matches: &Matches) -> Result<(), CliError> {
+        let code = matches.positionals.first().map_or("", String::as_str);
+        match code.parse::<ErrorCode>() {
+            Ok(code) => {
+                let mut out = io::stdout().lock();
+                writeln!(out, "{}: {}\n", code, code.title())?;
+                write!(out, "{}", code.explanation())?;
+                Ok(())
+            }
+            Err(e) => {
+                let known: Vec<&str> = ErrorCode::all().iter().map(|c| c.as_str()).collect();
+                Err(CliError::Usage(format!(
+                    "{}\nKnown codes: {}",
+                    e,
+                    known.join(", ")
+                )))
+            }
+        }
+    }
+}
@@ -0,0 +1,122 @@
+mod evaluate;
+mod explain;
+mod parse;
+mod run;
+mod tokenize;
+
+use std::io::{self, IsTerminal};
+
+use super::args::{GlobalOptions, Matches, OptionSpec};
+use super::{CliError, Input, Source};
+use crate::errors::{ColorChoice, ErrorHandler};
+use crate::interpreter::Unwind;
+use crate::Options;
+
+// A subcommand of the interpreter. Adding a command means implementing this
+// trait and listing it in `registry`. Commands run on the interpreter's own
+// thread, so they must be `Sync`.
+pub trait Command: Sync {
+    fn name(&self) -> &'static str;
+    fn about(&self) -> &'static str;
+    // operands shown in the usage line, e.g. `<file>`
+    fn operands(&self) -> &'static str {
+        "(<file> | - | -e <code>)"
+    }
+    fn options(&self) -> &'static [OptionSpec] {
+        &[]
+    }
+    fn run(&self, ctx: &Context, matches: &Matches) -> Result<(), CliError>;
+}
+
+pub fn registry() -> Vec<Box<dyn Command>> {
+    vec![
+        Box::new(tokenize::Tokenize),
+        Box::new(parse::Parse),
+        Box::new(evaluate::Evaluate),
+        Box::new(run::Run),
+        Box::new(explain::Explain),
+    ]
+}
+
+pub fn find(name: &str) -> Option<Box<dyn Command>> {
+    registry()
+        .into_iter()
+        .find(|command| command.name() == name)
+}
+
+// Taken by every command that runs Lox code; see `Context::runtime_error`.
+const NO_TRACEBACK: OptionSpec = OptionSpec {
+    name: "no-traceback",
+    value: None,
+    help: "report runtime errors without help notes or the stack traceback",
+};
+
+pub struct Context {
+    pub global: GlobalOptions,
+}
+
+impl Context {
+    // The source named by `-e`, `-` or the command's first operand.
+    pub fn source(&self, matches: &Matches) -> Result<Source, CliError> {
+        let input = match (&self.global.eval, matches.positionals.first()) {
+            (Some(code), None) => Input::Eval(code.clone()),
+            (None, Some(filename)) => Input::from_arg(filename),
+            (Some(_), Some(_)) => {
+                return Err(CliError::Usage(String::from(
+                    "-e cannot be combined with a filename",
+                )))
+            }
+            (None, None) => return Err(CliError::Usage(String::from("no input file given"))),
+        };
+        input.read()
+    }
+
+    pub fn options(&self) -> Options {
+        Options {
+            dialect: self.global.dialect,
+            lints: self.global.lints.clone(),
+        }
+    }
+
+    pub fn error_handler(&self, source: &Source) -> ErrorHandler {
+        let mut error_handler = ErrorHandler::with_max_errors(self.global.max_errors);
+        error_handler.set_lints(self.global.lints.clone());
+        error_handler.set_format(self.global.error_format);
+        error_handler.set_color(match self.global.color {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => io::stderr().is_terminal(),
+        });
+        error_handler.set_source_name(source.name.clone());
+        error_handler.set_name_in_short_format(source.is_pseudo_file());
+        error_handler
+    }
+
+    // Reports why the program stopped and picks the matching exit status.
+    // Without the help notes and traceback the report of a file's error
+    // matches the book's jlox exactly. Stdin and `-e` code put their name
+    // before the line number, as their short compile errors do.
+    pub fn runtime_error(&self, source: &Source, matches: &Matches, unwind: Unwind) -> CliError {
+        let traceback = !matches.is_set(NO_TRACEBACK.name);
+        match unwind {
+            Unwind::Error(error) => {
+                if source.is_pseudo_file() {
+                    eprintln!("{}\n{}: [line {}]", error.message, source.name, error.line);
+                } else {
+                    eprintln!("{}", error);
+                }
+                if traceback {
+                    for note in &error.help {
+                        eprintln!("  = help: {}", note);
+                    }
+                    for line in error.traceback() {
+                        eprintln!("{}", line);
+                    }
+                }
+                CliError::Runtime
+            }
+            Unwind::Io(error) => CliError::Io(error),
+            Unwind::Return(_) => unreachable!("`return` never unwinds past a call or the program"),
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use std::io::{self, Write};
+
+use super::{Command, Context};
+use crate::ast::format::AstFormat;
+use crate::ast::unparse::{unparse_expression, unparse_program};
+use crate::cli::args::{Matches, OptionSpec};
+use crate::cli::CliError;
+use crate::errors::Diagnostic;
+use crate::json::Json;
+use crate::{parse_expression_with, parse_program_with};
+
+pub struct Parse;
+
+impl Command for Parse {
+    fn name(&self) -> &'static str {
+        "parse"
+    }
+
+    fn about(&self) -> &'static str {
+        "Parse a Lox expression, or a program with --program, and print its syntax tree"
+    }
+
+    fn options(&self) -> &'static [OptionSpec] {
+        &[
+            OptionSpec {
+                name: "program",
+                value: None,
+                help: "parse a whole program instead of a single expression",
+            },
+            OptionSpec {
+                name: "format",
+                value: Some("book|sexpr|json|lox"),
+                help: "syntax tree output format (default: book)",
+            },
+        ]
+    }
+
+    fn run(&self, ctx: &Context, matches: &Matches) -> Result<(), CliError> {
+        let format = match matches.value("format") {
+            Some(format) => format.parse::<AstFormat>().map_err(CliError::Usage)?,
+            None => AstFormat::default(),
+        };
+        let source = ctx.source(matches)?;
+        let (lines, diagnostics): (Option<Vec<String>>, Vec<Diagnostic>) =
+            if matches.is_set("program") {
+                let result = parse_program_with(&source.text, &ctx.options());
+                let lines = result.ast.map(|ast| match format {
+                    AstFormat::Book => ast
+                        .roots()
+                        .iter()
+                        .map(|id| ast.display(*id).to_string())
+                        .collect(),
+                    AstFormat::Sexpr => ast.roots().iter().map(|id| ast.to_sexpr(*id)).collect(),
+                    AstFormat::Json => {
+                        let statements = ast.roots().iter().map(|id| ast.to_json(*id)).collect();
+                        vec![Json::Array(statements).pretty()]
+                    }
+                    AstFormat::Lox => unparse_program(&ast).lines().map(String::from).collect(),
+                });
+                (lines, result.diagnostics)
+            } else {
+                let result = parse_expression_with(&source.text, &ctx.options());
+                let lines = result.ast.map(|ast| match format {
+                    AstFormat::Book => vec![ast.display(ast.root()).to_string()],
+                    AstFormat::Sexpr => vec![ast.to_sexpr(ast.root())],
+                    AstFormat::Json => vec![ast.to_json(ast.root()).pretty()],
+                    AstFormat::Lox => vec![unparse_expression(&ast)],
+                });
+                (lines, result.diagnostics)
+            };
+        let mut error_handler = ctx.error_handler(&source);
+        error_handler.extend(diagnostics);
+        error_handler.display_errors(&source.text);
+        match lines {
+            Some(lines) if !error_handler.has_error() => {
+                let mut out = io::stdout().lock();
+                for line in lines {
+                    writeln!(out, "{}", line)?;
+                }
+                Ok(())
+            }
+            _ => Err(CliError::Compile),
+        }
+    }
+}
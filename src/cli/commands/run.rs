@@ -0,0 +1,47 @@
+use super::{Command, Context, NO_TRACEBACK};
+use crate::cli::args::{Matches, OptionSpec};
+use crate::cli::CliError;
+use crate::interpreter::Interpreter;
+use crate::{parse_program_with, resolve_with};
+
+pub struct Run;
+
+impl Command for Run {
+    fn name(&self) -> &'static str {
+        "run"
+    }
+
+    fn about(&self) -> &'static str {
+        "Run a Lox program"
+    }
+
+    fn options(&self) -> &'static [OptionSpec] {
+        &[NO_TRACEBACK]
+    }
+
+    fn run(&self, ctx: &Context, matches: &Matches) -> Result<(), CliError> {
+        let source = ctx.source(matches)?;
+        let options = ctx.options();
+        let result = parse_program_with(&source.text, &options);
+        let mut error_handler = ctx.error_handler(&source);
+        error_handler.extend(result.diagnostics);
+        let resolved = match result.ast {
+            Some(ast) if !error_handler.has_error() => {
+                let resolved = resolve_with(&ast, &options);
+                error_handler.extend(resolved.diagnostics);
+                Some((ast, resolved.locals))
+            }
+            _ => None,
+        };
+        error_handler.display_errors(&source.text);
+        let (ast, locals) = match resolved {
+            Some(resolved) if !error_handler.has_error() => resolved,
+            _ => return Err(CliError::Compile),
+        };
+        let mut interpreter = Interpreter::new();
+        interpreter.set_locals(locals);
+        interpreter
+            .interpret(ast)
+            .map_err(|unwind| ctx.runtime_error(&source, matches, unwind))
+    }
+}
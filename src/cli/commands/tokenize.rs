@@ -0,0 +1,44 @@
+use std::io;
+
+use super::{Command, Context};
+use crate::cli::args::{Matches, OptionSpec};
+use crate::cli::CliError;
+use crate::scan_with;
+use crate::token::format::{write_tokens, TokenFormat};
+
+pub struct Tokenize;
+
+impl Command for Tokenize {
+    fn name(&self) -> &'static str {
+        "tokenize"
+    }
+
+    fn about(&self) -> &'static str {
+        "Print the tokens of a Lox program"
+    }
+
+    fn options(&self) -> &'static [OptionSpec] {
+        &[OptionSpec {
+            name: "format",
+            value: Some("text|json|ndjson|table"),
+            help: "token output format (default: text)",
+        }]
+    }
+
+    fn run(&self, ctx: &Context, matches: &Matches) -> Result<(), CliError> {
+        let format = match matches.value("format") {
+            Some(format) => format.parse::<TokenFormat>().map_err(CliError::Usage)?,
+            None => TokenFormat::default(),
+        };
+        let source = ctx.source(matches)?;
+        let result = scan_with(&source.text, &ctx.options());
+        let mut error_handler = ctx.error_handler(&source);
+        error_handler.extend(result.diagnostics);
+        error_handler.display_errors(&source.text);
+        write_tokens(&mut io::stdout().lock(), &result.tokens, format)?;
+        if error_handler.has_error() {
+            return Err(CliError::Compile);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,64 @@
+use std::io;
+use thiserror::Error;
+
+// Exit codes follow the BSD sysexits.h convention used by the book's jlox.
+pub const EX_OK: i32 = 0;
+pub const EX_USAGE: i32 = 64;
+pub const EX_DATAERR: i32 = 65;
+pub const EX_NOINPUT: i32 = 66;
+pub const EX_SOFTWARE: i32 = 70;
+pub const EX_IOERR: i32 = 74;
+
+#[derive(Debug, Error)]
+pub enum CliError {
+    #[error("{0}")]
+    Usage(String),
+    // the diagnostics themselves have already been displayed
+    #[error("could not compile due to previous errors")]
+    Compile,
+    #[error("runtime error")]
+    Runtime,
+    #[error("{name}: {source}")]
+    NoInput { name: String, source: io::Error },
+    #[error("{name}: source is not valid UTF-8: invalid byte at offset {offset}")]
+    InvalidUtf8 { name: String, offset: usize },
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+}
+
+impl CliError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CliError::Usage(_) => EX_USAGE,
+            CliError::Compile | CliError::InvalidUtf8 { .. } => EX_DATAERR,
+            CliError::NoInput { .. } => EX_NOINPUT,
+            CliError::Runtime => EX_SOFTWARE,
+            CliError::Io(_) => EX_IOERR,
+        }
+    }
+
+    // Compile and runtime failures are reported through the ErrorHandler
+    // before the error reaches main, so there is nothing left to print.
+    pub fn is_reported(&self) -> bool {
+        matches!(self, CliError::Compile | CliError::Runtime)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_exit_codes() {
+        assert_eq!(64, CliError::Usage(String::from("bad")).exit_code());
+        assert_eq!(65, CliError::Compile.exit_code());
+        assert_eq!(70, CliError::Runtime.exit_code());
+        let not_found = CliError::NoInput {
+            name: String::from("missing.lox"),
+            source: io::Error::from(io::ErrorKind::NotFound),
+        };
+        assert_eq!(66, not_found.exit_code());
+        let broken_pipe = CliError::from(io::Error::from(io::ErrorKind::BrokenPipe));
+        assert_eq!(74, broken_pipe.exit_code());
+    }
+}
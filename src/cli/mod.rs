@@ -0,0 +1,191 @@
+pub mod args;
+pub mod commands;
+pub mod error;
+
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+
+use crate::interpreter;
+use args::{parse_args, OptionSpec, GLOBAL_OPTIONS};
+use commands::{Command, Context};
+pub use error::CliError;
+
+pub const STDIN_NAME: &str = "<stdin>";
+pub const EVAL_NAME: &str = "<eval>";
+
+// Where the Lox source comes from: a file, `-` for stdin or `-e <code>`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Input {
+    File(PathBuf),
+    Stdin,
+    Eval(String),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Source {
+    pub name: String,
+    pub text: String,
+    pseudo_file: bool,
+}
+
+impl Source {
+    pub fn is_pseudo_file(&self) -> bool {
+        self.pseudo_file
+    }
+}
+
+impl Input {
+    pub fn from_arg(arg: &str) -> Self {
+        if arg == "-" {
+            Input::Stdin
+        } else {
+            Input::File(PathBuf::from(arg))
+        }
+    }
+
+    // Stdin and `-e` code have no file name of their own, so their short
+    // diagnostics say where they came from. Files keep the plain format.
+    pub fn is_pseudo_file(&self) -> bool {
+        !matches!(self, Input::File(_))
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Input::File(path) => path.display().to_string(),
+            Input::Stdin => String::from(STDIN_NAME),
+            Input::Eval(_) => String::from(EVAL_NAME),
+        }
+    }
+
+    pub fn read(&self) -> Result<Source, CliError> {
+        let bytes = match self {
+            Input::File(path) => fs::read(path).map_err(|source| match source.kind() {
+                io::ErrorKind::NotFound | io::ErrorKind::PermissionDenied => CliError::NoInput {
+                    name: self.name(),
+                    source,
+                },
+                _ => CliError::Io(source),
+            })?,
+            Input::Stdin => {
+                let mut bytes = Vec::new();
+                io::stdin().lock().read_to_end(&mut bytes)?;
+                bytes
+            }
+            Input::Eval(code) => code.clone().into_bytes(),
+        };
+        let text = String::from_utf8(bytes).map_err(|e| CliError::InvalidUtf8 {
+            name: self.name(),
+            offset: e.utf8_error().valid_up_to(),
+        })?;
+        Ok(Source {
+            name: self.name(),
+            text,
+            pseudo_file: self.is_pseudo_file(),
+        })
+    }
+}
+
+const PROGRAM: &str = env!("CARGO_PKG_NAME");
+
+// Entry point of the binary: `args` excludes the program name.
+pub fn run(args: &[String]) -> Result<(), CliError> {
+    let parsed = parse_args(args, |name| commands::find(name).map(|c| c.options()))?;
+    let command = parsed.command.as_deref().and_then(commands::find);
+    let mut out = io::stdout().lock();
+    if parsed.global.version {
+        writeln!(out, "{} {}", PROGRAM, env!("CARGO_PKG_VERSION"))?;
+        return Ok(());
+    }
+    if parsed.global.help {
+        write!(out, "{}", help(command.as_deref()))?;
+        return Ok(());
+    }
+    drop(out);
+    let Some(command) = command else {
+        return Err(CliError::Usage(help(None).trim_end().to_string()));
+    };
+    let ctx = Context {
+        global: parsed.global,
+    };
+    interpreter::with_stack(|| command.run(&ctx, &parsed.matches))?
+}
+
+fn options_help(options: &[OptionSpec], text: &mut String) {
+    for option in options {
+        text.push_str(&format!("  {:<32}{}\n", option.usage(), option.help));
+    }
+}
+
+pub fn help(command: Option<&dyn Command>) -> String {
+    let mut text = String::new();
+    match command {
+        Some(command) => {
+            text.push_str(&format!("{}\n\n", command.about()));
+            text.push_str(&format!(
+                "Usage: {} {} [OPTIONS] {}\n",
+                PROGRAM,
+                command.name(),
+                command.operands()
+            ));
+            if !command.options().is_empty() {
+                text.push_str("\nOptions:\n");
+                options_help(command.options(), &mut text);
+            }
+        }
+        None => {
+            text.push_str(&format!(
+                "Usage: {} [OPTIONS] <COMMAND> ...\n\nCommands:\n",
+                PROGRAM
+            ));
+            for command in commands::registry() {
+                text.push_str(&format!("  {:<32}{}\n", command.name(), command.about()));
+            }
+        }
+    }
+    text.push_str("\nGlobal options:\n");
+    options_help(GLOBAL_OPTIONS, &mut text);
+    for (flag, help) in [
+        (
+            "-e <code>",
+            "read the program from <code> instead of a file",
+        ),
+        ("-W <lint>", "warn on <lint>; `warnings` means every lint"),
+        ("-A <lint>", "allow <lint>"),
+        ("-D <lint>", "deny <lint>, turning it into an error"),
+    ] {
+        text.push_str(&format!("  {:<32}{}\n", flag, help));
+    }
+    text
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn test_read_source_errors() {
+        let dir = env::temp_dir();
+        let missing = Input::File(dir.join("lox-definitely-missing.lox"));
+        assert_eq!(66, missing.read().unwrap_err().exit_code());
+
+        let path = dir.join(format!("lox-invalid-{}.lox", std::process::id()));
+        fs::write(&path, b"var a\xff = 1;").unwrap();
+        let err = Input::File(path.clone()).read().unwrap_err();
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(err, CliError::InvalidUtf8 { offset: 5, .. }));
+        assert_eq!(65, err.exit_code());
+    }
+
+    #[test]
+    fn test_pseudo_files() {
+        assert_eq!(Input::Stdin, Input::from_arg("-"));
+        let source = Input::Eval(String::from("print 1;")).read().unwrap();
+        assert_eq!("<eval>", source.name);
+        assert_eq!("print 1;", source.text);
+        assert!(source.is_pseudo_file());
+        // a file that happens to have a pseudo-file's name is still a file
+        assert!(!Input::from_arg(STDIN_NAME).is_pseudo_file());
+    }
+}
@@ -0,0 +1,497 @@
+use std::fmt;
+use std::fmt::Display;
+use std::str::FromStr;
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum ErrorCode {
+    UnexpectedCharacter,
+    UnterminatedString,
+    InvalidNumber,
+    ConfusableIdentifier,
+    UnusedVariable,
+    Shadowing,
+    ExpectedExpression,
+    UnexpectedToken,
+    InvalidAssignmentTarget,
+    TooManyArguments,
+    ReadInOwnInitializer,
+    Redeclaration,
+    ReturnOutsideFunction,
+    ThisOutsideClass,
+    SuperOutsideClass,
+    ReturnFromInitializer,
+    InheritFromSelf,
+    SuperWithoutSuperclass,
+}
+
+struct Explanation {
+    code: &'static str,
+    title: &'static str,
+    body: &'static str,
+}
+
+impl ErrorCode {
+    // Every code, in numeric order.
+    pub const ALL: [ErrorCode; 18] = [
+        ErrorCode::UnexpectedCharacter,
+        ErrorCode::UnterminatedString,
+        ErrorCode::InvalidNumber,
+        ErrorCode::ConfusableIdentifier,
+        ErrorCode::UnusedVariable,
+        ErrorCode::Shadowing,
+        ErrorCode::ExpectedExpression,
+        ErrorCode::UnexpectedToken,
+        ErrorCode::InvalidAssignmentTarget,
+        ErrorCode::TooManyArguments,
+        ErrorCode::ReadInOwnInitializer,
+        ErrorCode::Redeclaration,
+        ErrorCode::ReturnOutsideFunction,
+        ErrorCode::ThisOutsideClass,
+        ErrorCode::SuperOutsideClass,
+        ErrorCode::ReturnFromInitializer,
+        ErrorCode::InheritFromSelf,
+        ErrorCode::SuperWithoutSuperclass,
+    ];
+
+    fn info(&self) -> Explanation {
+        match self {
+            ErrorCode::UnexpectedCharacter => Explanation {
+                code: "L0001",
+                title: "Unexpected character",
+                body: "\
+The scanner found a character that is not part of the Lox language.
+Lox source may only contain letters, digits, `_`, whitespace, string
+literals and the punctuation used by its operators.
+
+Erroneous code example:
+
+    var price = 10$;
+
+Characters such as `$`, `#`, `@` or `^` have no meaning outside of a
+string. Remove the character, or put it inside a string literal:
+
+    var price = 10;
+    print \"10$\";
+",
+            },
+            ErrorCode::UnterminatedString => Explanation {
+                code: "L0002",
+                title: "Unterminated string",
+                body: "\
+A string literal was opened with `\"` but the file ended before the
+closing `\"` was found.
+
+Erroneous code example:
+
+    print \"hello;
+
+Strings may span several lines, so a missing quote swallows everything
+up to the end of the file. Close the string where it was meant to end:
+
+    print \"hello\";
+",
+            },
+            ErrorCode::InvalidNumber => Explanation {
+                code: "L0003",
+                title: "Invalid number literal",
+                body: "\
+A run of digits and dots could not be read as a number. Lox numbers
+are written as digits with at most one decimal point.
+
+Erroneous code example:
+
+    var version = 1.2.3;
+
+Use a single decimal point, or a string if the value is not a number:
+
+    var version = 1.2;
+    var release = \"1.2.3\";
+",
+            },
+            ErrorCode::ConfusableIdentifier => Explanation {
+                code: "L0004",
+                title: "Confusable identifier",
+                body: "\
+An identifier contains a character that looks like an ASCII letter but
+is a different Unicode character, such as the Cyrillic `а` (U+0430)
+instead of the Latin `a`. Two names that print the same may then refer
+to different variables.
+
+Erroneous code example:
+
+    var pаy = 10; // the `а` here is Cyrillic
+    print pay;    // Undefined variable 'pay'.
+
+Retype the name using ASCII letters only:
+
+    var pay = 10;
+    print pay;
+
+This is the `confusable-identifiers` lint, a warning by default.
+",
+            },
+            ErrorCode::UnusedVariable => Explanation {
+                code: "L0005",
+                title: "Unused variable",
+                body: "\
+A local variable is declared but never read. This is often a typo in a
+later use of the name, or leftover code.
+
+Erroneous code example:
+
+    fun area(w, h) {
+        var result = w * h;
+        return w * h;
+    }
+
+Use the variable, or remove it:
+
+    fun area(w, h) {
+        var result = w * h;
+        return result;
+    }
+
+This is the `unused-variables` lint, allowed by default. Enable it with
+`-W unused-variables`.
+",
+            },
+            ErrorCode::Shadowing => Explanation {
+                code: "L0006",
+                title: "Shadowed variable",
+                body: "\
+A local variable has the same name as a variable in an enclosing scope,
+hiding the outer one for the rest of the block.
+
+Erroneous code example:
+
+    fun report(items) {
+        var total = 0;
+        {
+            var total = items;
+            print total;
+        }
+    }
+
+Give the inner variable its own name:
+
+    fun report(items) {
+        var total = 0;
+        {
+            var count = items;
+            print count;
+        }
+    }
+
+This is the `shadowing` lint, allowed by default. Enable it with
+`-W shadowing`.
+",
+            },
+            ErrorCode::ExpectedExpression => Explanation {
+                code: "L0007",
+                title: "Expected expression",
+                body: "\
+The parser needed a value at this point, such as a number, a string, a
+variable or a parenthesized expression, but found something else.
+
+Erroneous code example:
+
+    print (1 + );
+    print * 2;
+
+Every operator needs operands on both sides, except the prefix
+operators `!` and `-` which need one on their right:
+
+    print (1 + 2);
+    print -2;
+",
+            },
+            ErrorCode::UnexpectedToken => Explanation {
+                code: "L0008",
+                title: "Unexpected token",
+                body: "\
+The parser expected a particular token, such as a closing `)` or a `;`,
+and found a different one.
+
+Erroneous code example:
+
+    print (1 + 2;
+
+Parentheses, braces and statements must be closed:
+
+    print (1 + 2);
+",
+            },
+            ErrorCode::InvalidAssignmentTarget => Explanation {
+                code: "L0009",
+                title: "Invalid assignment target",
+                body: "\
+The left side of `=` must be a variable or a property. Any other
+expression has nowhere to store the value.
+
+Erroneous code example:
+
+    a + b = 3;
+    (x) = 1;
+
+Assign to a variable or a field instead:
+
+    a = 3 - b;
+    x = 1;
+    point.x = 1;
+",
+            },
+            ErrorCode::TooManyArguments => Explanation {
+                code: "L0010",
+                title: "Too many arguments or parameters",
+                body: "\
+A function call or declaration has more than 255 arguments or
+parameters, the most Lox supports.
+
+Erroneous code example:
+
+    fun f(a1, a2, a3, /* ... */ a256) {}
+
+Group related values into an instance and pass that instead:
+
+    class Options {}
+    fun f(options) {}
+",
+            },
+            ErrorCode::ReadInOwnInitializer => Explanation {
+                code: "L0011",
+                title: "Local variable read in its own initializer",
+                body: "\
+A local variable is used in the expression that gives it its first
+value. The new variable already hides any outer one with the same name,
+but has no value yet.
+
+Erroneous code example:
+
+    var a = 1;
+    {
+        var a = a + 1;
+    }
+
+Give the inner variable a different name:
+
+    var a = 1;
+    {
+        var b = a + 1;
+    }
+",
+            },
+            ErrorCode::Redeclaration => Explanation {
+                code: "L0012",
+                title: "Variable declared twice in the same scope",
+                body: "\
+A block or function declares two local variables with the same name.
+Only global variables may be declared again.
+
+Erroneous code example:
+
+    fun f() {
+        var a = 1;
+        var a = 2;
+    }
+
+Assign to the existing variable instead:
+
+    fun f() {
+        var a = 1;
+        a = 2;
+    }
+",
+            },
+            ErrorCode::ReturnOutsideFunction => Explanation {
+                code: "L0013",
+                title: "Return outside of a function",
+                body: "\
+A `return` statement appears in top-level code, where there is no
+function to return from.
+
+Erroneous code example:
+
+    print \"start\";
+    return;
+
+Move the code into a function, or remove the `return`:
+
+    fun main() {
+        print \"start\";
+        return;
+    }
+    main();
+",
+            },
+            ErrorCode::ThisOutsideClass => Explanation {
+                code: "L0014",
+                title: "`this` outside of a class",
+                body: "\
+`this` refers to the instance a method was called on, so it can only be
+used inside the methods of a class.
+
+Erroneous code example:
+
+    fun describe() {
+        print this.name;
+    }
+
+Make the function a method, or pass the instance as a parameter:
+
+    fun describe(thing) {
+        print thing.name;
+    }
+",
+            },
+            ErrorCode::SuperOutsideClass => Explanation {
+                code: "L0015",
+                title: "`super` outside of a class",
+                body: "\
+`super` looks up a method on the superclass of the class it appears in,
+so it can only be used inside the methods of a class.
+
+Erroneous code example:
+
+    fun greet() {
+        super.greet();
+    }
+
+Call the method on an instance instead:
+
+    fun greet(person) {
+        person.greet();
+    }
+",
+            },
+            ErrorCode::ReturnFromInitializer => Explanation {
+                code: "L0016",
+                title: "Value returned from an initializer",
+                body: "\
+An `init` method returns a value. Calling a class always produces the
+new instance, so `init` may only use a bare `return;` to stop early.
+
+Erroneous code example:
+
+    class Point {
+        init(x, y) {
+            this.x = x;
+            this.y = y;
+            return this.x;
+        }
+    }
+
+Remove the value from the `return`:
+
+    class Point {
+        init(x, y) {
+            this.x = x;
+            this.y = y;
+            return;
+        }
+    }
+",
+            },
+            ErrorCode::InheritFromSelf => Explanation {
+                code: "L0017",
+                title: "Class inherits from itself",
+                body: "\
+A class names itself as its superclass. A class can only inherit from a
+different class that has already been declared.
+
+Erroneous code example:
+
+    class Node < Node {}
+
+Inherit from another class, or from nothing:
+
+    class Base {}
+    class Node < Base {}
+",
+            },
+            ErrorCode::SuperWithoutSuperclass => Explanation {
+                code: "L0018",
+                title: "`super` in a class with no superclass",
+                body: "\
+A method uses `super`, but its class does not inherit from another
+class, so there is no superclass to look the method up on.
+
+Erroneous code example:
+
+    class Doughnut {
+        cook() {
+            super.cook();
+        }
+    }
+
+Declare the superclass with `<`:
+
+    class Pastry {
+        cook() {}
+    }
+    class Doughnut < Pastry {
+        cook() {
+            super.cook();
+        }
+    }
+",
+            },
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        self.info().code
+    }
+
+    pub fn title(&self) -> &'static str {
+        self.info().title
+    }
+
+    pub fn explanation(&self) -> &'static str {
+        self.info().body
+    }
+
+    pub fn all() -> Vec<ErrorCode> {
+        ErrorCode::ALL.to_vec()
+    }
+}
+
+impl FromStr for ErrorCode {
+    type Err = String;
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        let wanted = code.to_uppercase();
+        ErrorCode::ALL
+            .into_iter()
+            .find(|error_code| error_code.as_str() == wanted)
+            .ok_or_else(|| format!("no error code named {}", code))
+    }
+}
+
+impl Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_every_code_is_registered() {
+        for (i, code) in ErrorCode::all().into_iter().enumerate() {
+            // numbered without gaps, so a code missing from ALL shows up
+            assert_eq!(format!("L{:04}", i + 1), code.as_str());
+            assert_eq!(code, code.as_str().parse::<ErrorCode>().unwrap());
+            assert!(code.explanation().contains("Erroneous code example"));
+        }
+    }
+
+    #[test]
+    fn test_lookup() {
+        assert_eq!(
+            Ok(ErrorCode::UnexpectedCharacter),
+            "l0001".parse::<ErrorCode>()
+        );
+        assert!("L9999".parse::<ErrorCode>().is_err());
+    }
+}
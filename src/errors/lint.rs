@@ -0,0 +1,199 @@
+use maplit::hashmap;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use super::ErrorCode;
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Lint {
+    ConfusableIdentifiers,
+    UnusedVariables,
+    Shadowing,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Level {
+    Allow,
+    Warn,
+    Deny,
+}
+
+struct LintInfo {
+    name: &'static str,
+    code: ErrorCode,
+    default_level: Level,
+}
+
+// Non-ASCII letters that render like an ASCII one, mostly Cyrillic and Greek.
+static CONFUSABLES: Lazy<HashMap<char, char>> = Lazy::new(|| {
+    hashmap! {
+        'а' => 'a', 'е' => 'e', 'о' => 'o', 'р' => 'p', 'с' => 'c', 'у' => 'y',
+        'х' => 'x', 'і' => 'i', 'ј' => 'j', 'ѕ' => 's', 'ԁ' => 'd', 'һ' => 'h',
+        'А' => 'A', 'В' => 'B', 'Е' => 'E', 'К' => 'K', 'М' => 'M', 'Н' => 'H',
+        'О' => 'O', 'Р' => 'P', 'С' => 'C', 'Т' => 'T', 'Х' => 'X', 'Ѕ' => 'S',
+        'α' => 'a', 'ο' => 'o', 'ν' => 'v', 'ι' => 'i', 'κ' => 'k', 'ρ' => 'p',
+        'Α' => 'A', 'Β' => 'B', 'Ε' => 'E', 'Ζ' => 'Z', 'Η' => 'H', 'Ι' => 'I',
+        'Κ' => 'K', 'Μ' => 'M', 'Ν' => 'N', 'Ο' => 'O', 'Ρ' => 'P', 'Τ' => 'T',
+        'Υ' => 'Y', 'Χ' => 'X',
+    }
+});
+
+pub fn confusable_with(c: char) -> Option<char> {
+    CONFUSABLES.get(&c).copied()
+}
+
+// Passing this name to -W/-A/-D sets every lint at once.
+pub const ALL_LINTS: &str = "warnings";
+
+impl Lint {
+    pub const ALL: [Lint; 3] = [
+        Lint::ConfusableIdentifiers,
+        Lint::UnusedVariables,
+        Lint::Shadowing,
+    ];
+
+    fn info(&self) -> LintInfo {
+        match self {
+            Lint::ConfusableIdentifiers => LintInfo {
+                name: "confusable-identifiers",
+                code: ErrorCode::ConfusableIdentifier,
+                default_level: Level::Warn,
+            },
+            Lint::UnusedVariables => LintInfo {
+                name: "unused-variables",
+                code: ErrorCode::UnusedVariable,
+                default_level: Level::Allow,
+            },
+            Lint::Shadowing => LintInfo {
+                name: "shadowing",
+                code: ErrorCode::Shadowing,
+                default_level: Level::Allow,
+            },
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.info().name
+    }
+
+    pub fn code(&self) -> ErrorCode {
+        self.info().code
+    }
+
+    pub fn default_level(&self) -> Level {
+        self.info().default_level
+    }
+
+    // Sorted by name.
+    pub fn all() -> Vec<Lint> {
+        let mut lints = Lint::ALL.to_vec();
+        lints.sort_by_key(|lint| lint.name());
+        lints
+    }
+}
+
+impl FromStr for Lint {
+    type Err = String;
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let name = name.replace('_', "-");
+        Lint::ALL
+            .into_iter()
+            .find(|lint| lint.name() == name)
+            .ok_or_else(|| format!("unknown lint: {}", name))
+    }
+}
+
+impl Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let level = match self {
+            Level::Allow => "allow",
+            Level::Warn => "warn",
+            Level::Deny => "deny",
+        };
+        write!(f, "{}", level)
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct LintConfig {
+    levels: HashMap<Lint, Level>,
+}
+
+impl LintConfig {
+    pub fn new() -> Self {
+        LintConfig::default()
+    }
+
+    pub fn level(&self, lint: Lint) -> Level {
+        self.levels
+            .get(&lint)
+            .copied()
+            .unwrap_or_else(|| lint.default_level())
+    }
+
+    pub fn set(&mut self, lint: Lint, level: Level) {
+        self.levels.insert(lint, level);
+    }
+
+    // Applies a command line flag such as `-D shadowing`. Later flags win,
+    // so `-D warnings -A shadowing` denies everything except shadowing.
+    pub fn apply_flag(&mut self, flag: &str, name: &str) -> Result<(), String> {
+        let level = match flag {
+            "-A" => Level::Allow,
+            "-W" => Level::Warn,
+            "-D" => Level::Deny,
+            other => return Err(format!("unknown lint flag: {}", other)),
+        };
+        if name == ALL_LINTS {
+            for lint in Lint::all() {
+                self.set(lint, level);
+            }
+        } else {
+            self.set(name.parse::<Lint>()?, level);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_lint_names() {
+        for lint in Lint::all() {
+            assert_eq!(Ok(lint), lint.name().parse::<Lint>());
+        }
+        assert_eq!(Ok(Lint::UnusedVariables), "unused_variables".parse());
+        assert!("unused".parse::<Lint>().is_err());
+    }
+
+    #[test]
+    fn test_confusables() {
+        assert_eq!(Some('a'), confusable_with('\u{0430}'));
+        assert_eq!(None, confusable_with('a'));
+        assert_eq!(None, confusable_with('é'));
+    }
+
+    #[test]
+    fn test_flags() {
+        let mut config = LintConfig::new();
+        assert_eq!(Level::Allow, config.level(Lint::Shadowing));
+        assert_eq!(Level::Allow, config.level(Lint::UnusedVariables));
+        config.apply_flag("-D", "warnings").unwrap();
+        config.apply_flag("-A", "shadowing").unwrap();
+        assert_eq!(Level::Deny, config.level(Lint::UnusedVariables));
+        assert_eq!(Level::Allow, config.level(Lint::Shadowing));
+        assert!(config.apply_flag("-X", "shadowing").is_err());
+        assert!(config.apply_flag("-W", "nope").is_err());
+    }
+}
@@ -1,36 +1,290 @@
-#[derive(Debug, Default)]
-struct LoxError {
-    error_msg: String,
-    line_num: usize,
+pub mod codes;
+pub mod lint;
+pub mod render;
+pub mod suggest;
+
+use std::collections::HashSet;
+use std::fmt;
+use std::fmt::Display;
+
+pub use codes::ErrorCode;
+use lint::{Level, Lint, LintConfig};
+pub use render::{ColorChoice, ErrorFormat};
+
+use crate::token::{Token, TokenType};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    // set when the diagnostic comes from a configurable lint
+    pub lint: Option<(Lint, Level)>,
+    pub code: ErrorCode,
+    pub error_msg: String,
+    pub line_num: usize,
+    // where on the line the error is, as in `Error at ')'`; empty if unknown
+    pub location: String,
+    // 1-based column range [start, end) on `line_num`, when known
+    pub columns: Option<(usize, usize)>,
+    // shown next to the carets in the human format
+    pub label: Option<String>,
+    pub help: Vec<String>,
+}
+
+// What two diagnostics share when one repeats the other.
+type DiagnosticKey = (usize, Option<(usize, usize)>, ErrorCode, String, String);
+
+impl Diagnostic {
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    fn key(&self) -> DiagnosticKey {
+        (
+            self.line_num,
+            self.columns,
+            self.code,
+            self.location.clone(),
+            self.error_msg.clone(),
+        )
+    }
+}
+
+// Invisible characters are written as escapes so the message shows what was
+// there; quotes and backslashes are left as they are.
+fn escape_char(c: char) -> String {
+    match c {
+        '\\' | '\'' | '"' => c.to_string(),
+        _ => c.escape_debug().to_string(),
+    }
+}
+
+// The short, single-line form without notes.
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self.severity {
+            Severity::Error => "Error",
+            Severity::Warning => "Warning",
+        };
+        write!(
+            f,
+            "[line {}] {}{}: {}",
+            self.line_num, label, self.location, self.error_msg
+        )
+    }
 }
+
 #[derive(Debug, Default)]
 pub struct ErrorHandler {
-    errors: Vec<LoxError>,
+    errors: Vec<Diagnostic>,
+    // keys of `errors`, so a repeated diagnostic is dropped
+    seen: HashSet<DiagnosticKey>,
+    max_errors: Option<usize>,
+    lints: LintConfig,
+    format: ErrorFormat,
+    color: bool,
+    source_name: Option<String>,
+    // whether short diagnostics start with the source name
+    name_in_short_format: bool,
+    // characters of the unexpected-character run that ends the error list
+    unexpected_run: String,
 }
 
 impl ErrorHandler {
     pub fn new() -> Self {
-        ErrorHandler { errors: Vec::new() }
+        ErrorHandler::default()
+    }
+    pub fn with_max_errors(max_errors: Option<usize>) -> Self {
+        ErrorHandler {
+            max_errors,
+            ..ErrorHandler::default()
+        }
+    }
+    pub fn set_lints(&mut self, lints: LintConfig) {
+        self.lints = lints;
+    }
+    pub fn set_format(&mut self, format: ErrorFormat) {
+        self.format = format;
+    }
+    pub fn set_color(&mut self, color: bool) {
+        self.color = color;
+    }
+    pub fn set_source_name(&mut self, source_name: String) {
+        self.source_name = Some(source_name);
+    }
+    pub fn set_name_in_short_format(&mut self, name_in_short_format: bool) {
+        self.name_in_short_format = name_in_short_format;
     }
     pub fn has_error(&self) -> bool {
-        !self.errors.is_empty()
+        self.errors.iter().any(Diagnostic::is_error)
+    }
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.errors
+    }
+    pub fn into_diagnostics(self) -> Vec<Diagnostic> {
+        self.errors
     }
-    pub fn report(&mut self, error_msg: String, line_num: usize) {
-        let lox_error = LoxError {
+    // Takes over diagnostics produced by another handler, e.g. one used by
+    // the library API, so they are rendered with this handler's settings.
+    pub fn extend(&mut self, diagnostics: Vec<Diagnostic>) {
+        for diagnostic in diagnostics {
+            self.push(diagnostic);
+        }
+    }
+    pub fn report(&mut self, code: ErrorCode, error_msg: String, line_num: usize) {
+        self.push(Diagnostic {
+            severity: Severity::Error,
+            lint: None,
+            code,
             error_msg,
             line_num,
+            location: String::new(),
+            columns: None,
+            label: None,
+            help: Vec::new(),
+        });
+    }
+
+    // Reports an error about a token, located the way the book does it:
+    // `at 'lexeme'`, or `at end` for the end of the file.
+    pub fn report_token(&mut self, code: ErrorCode, error_msg: String, token: &Token) {
+        let location = match token.token_type {
+            TokenType::EOF => String::from(" at end"),
+            _ => format!(" at '{}'", token.lexeme),
         };
-        self.errors.push(lox_error);
+        let width = token.lexeme.chars().count().max(1);
+        self.push(Diagnostic {
+            severity: Severity::Error,
+            lint: None,
+            code,
+            error_msg,
+            line_num: token.line,
+            location,
+            columns: Some((token.column, token.column + width)),
+            label: None,
+            help: Vec::new(),
+        });
+    }
+
+    // Reports a lint finding at the level configured for it: dropped when
+    // allowed, a warning when warned, and a full error when denied.
+    pub fn lint(&mut self, lint: Lint, error_msg: String, line_num: usize) {
+        let level = self.lints.level(lint);
+        let severity = match level {
+            Level::Allow => return,
+            Level::Warn => Severity::Warning,
+            Level::Deny => Severity::Error,
+        };
+        self.push(Diagnostic {
+            severity,
+            lint: Some((lint, level)),
+            code: lint.code(),
+            error_msg,
+            line_num,
+            location: String::new(),
+            columns: None,
+            label: None,
+            help: Vec::new(),
+        });
+    }
+
+    // Attaches a caret label to the most recently reported error.
+    pub fn add_label(&mut self, label: String) {
+        if let Some(last) = self.errors.last_mut() {
+            last.label = Some(label);
+        }
+    }
+
+    // Attaches a help note to the most recently reported error.
+    pub fn add_help(&mut self, note: String) {
+        if let Some(last) = self.errors.last_mut() {
+            if !last.help.contains(&note) {
+                last.help.push(note);
+            }
+        }
+    }
+
+    // Consecutive unexpected characters on a line are merged into one error
+    // spanning all of them, so a pasted blob is reported once.
+    pub fn report_unexpected_char(&mut self, c: char, line_num: usize, column: usize) {
+        if let Some(last) = self.errors.last_mut() {
+            if last.code == ErrorCode::UnexpectedCharacter
+                && last.line_num == line_num
+                && last.columns.map(|(_, end)| end) == Some(column)
+                && !self.unexpected_run.is_empty()
+            {
+                let start = last.columns.map_or(column, |(start, _)| start);
+                self.unexpected_run.push(c);
+                last.columns = Some((start, column + 1));
+                last.error_msg = format!(
+                    "Unexpected characters: {} (columns {}-{})",
+                    self.unexpected_run
+                        .chars()
+                        .map(escape_char)
+                        .collect::<String>(),
+                    start,
+                    column
+                );
+                return;
+            }
+        }
+        self.push(Diagnostic {
+            severity: Severity::Error,
+            lint: None,
+            code: ErrorCode::UnexpectedCharacter,
+            error_msg: format!("Unexpected character: {}", escape_char(c)),
+            line_num,
+            location: String::new(),
+            columns: Some((column, column + 1)),
+            label: None,
+            help: Vec::new(),
+        });
+        self.unexpected_run = c.to_string();
+    }
+
+    fn push(&mut self, diagnostic: Diagnostic) {
+        self.unexpected_run.clear();
+        if self.seen.insert(diagnostic.key()) {
+            self.errors.push(diagnostic);
+        }
+    }
+
+    pub fn codes(&self) -> Vec<ErrorCode> {
+        self.errors.iter().map(|err| err.code).collect()
+    }
+
+    pub fn render_errors(&self, source_code: &str) -> Vec<String> {
+        let shown = self.max_errors.unwrap_or(usize::MAX);
+        let source_name = self.source_name.as_deref().unwrap_or("<source>");
+        let mut lines: Vec<String> = Vec::new();
+        for err in self.errors.iter().take(shown) {
+            match self.format {
+                ErrorFormat::Short => {
+                    let name = Some(source_name).filter(|_| self.name_in_short_format);
+                    render::render_short(err, name, &mut lines)
+                }
+                ErrorFormat::Human => {
+                    render::render_human(err, source_name, source_code, self.color, &mut lines)
+                }
+            }
+        }
+        let suppressed = self.errors.len().saturating_sub(shown);
+        match suppressed {
+            0 => {}
+            1 => lines.push(String::from("1 more error suppressed")),
+            n => lines.push(format!("{} more errors suppressed", n)),
+        }
+        lines
     }
 
     pub fn display_errors(&self, source_code: &str) {
-        // let source_lines: Vec<&str> = source_code.lines().collect();
-        for err in &self.errors {
-            eprintln!(
-                "[line {}] Error: {}",
-                err.line_num,
-                err.error_msg // source_lines[err.line_num as usize - 1]
-            );
+        for line in self.render_errors(source_code) {
+            eprintln!("{}", line);
         }
     }
 }
@@ -48,8 +302,174 @@ print(\"abc\");
 var j = 094342;
 ";
         let mut error_handler = ErrorHandler::new();
-        error_handler.report(String::from("This is a simple error"), 2);
-        error_handler.report(String::from("This is error number 2"), 5);
+        error_handler.report(
+            ErrorCode::UnexpectedCharacter,
+            String::from("This is a simple error"),
+            2,
+        );
+        error_handler.report(
+            ErrorCode::InvalidNumber,
+            String::from("This is error number 2"),
+            5,
+        );
         error_handler.display_errors(source_code);
+        assert_eq!(
+            vec![ErrorCode::UnexpectedCharacter, ErrorCode::InvalidNumber],
+            error_handler.codes()
+        );
+    }
+
+    #[test]
+    fn test_unexpected_characters_are_merged() {
+        let mut error_handler = ErrorHandler::new();
+        error_handler.report_unexpected_char('$', 1, 3);
+        error_handler.report_unexpected_char('#', 1, 4);
+        error_handler.report_unexpected_char('@', 1, 5);
+        error_handler.report_unexpected_char('^', 1, 7);
+        error_handler.report_unexpected_char('%', 2, 8);
+        assert_eq!(
+            vec![
+                "[line 1] Error: Unexpected characters: $#@ (columns 3-5)",
+                "[line 1] Error: Unexpected character: ^",
+                "[line 2] Error: Unexpected character: %",
+            ],
+            error_handler.render_errors("")
+        );
+
+        let mut error_handler = ErrorHandler::new();
+        error_handler.report_unexpected_char('\u{0}', 1, 1);
+        error_handler.report_unexpected_char('\\', 2, 1);
+        error_handler.report_unexpected_char('\u{7}', 3, 1);
+        error_handler.report_unexpected_char('\'', 3, 2);
+        assert_eq!(
+            vec![
+                "[line 1] Error: Unexpected character: \\0",
+                "[line 2] Error: Unexpected character: \\",
+                "[line 3] Error: Unexpected characters: \\u{7}' (columns 1-2)",
+            ],
+            error_handler.render_errors("")
+        );
+    }
+
+    #[test]
+    fn test_duplicates_are_dropped() {
+        let mut error_handler = ErrorHandler::new();
+        for _ in 0..3 {
+            error_handler.report(
+                ErrorCode::UnterminatedString,
+                String::from("Unterminated string."),
+                4,
+            );
+        }
+        assert_eq!(
+            vec!["[line 4] Error: Unterminated string."],
+            error_handler.render_errors("")
+        );
+    }
+
+    #[test]
+    fn test_help_notes() {
+        let mut error_handler = ErrorHandler::new();
+        error_handler.report(
+            ErrorCode::UnterminatedString,
+            String::from("Unterminated string."),
+            1,
+        );
+        error_handler.add_help(String::from("close the string with `\"`"));
+        error_handler.add_help(String::from("close the string with `\"`"));
+        assert_eq!(
+            vec![String::from("close the string with `\"`")],
+            error_handler.diagnostics()[0].help
+        );
+        // only the human format shows help
+        assert_eq!(
+            vec!["[line 1] Error: Unterminated string."],
+            error_handler.render_errors("")
+        );
+    }
+
+    #[test]
+    fn test_lint_levels() {
+        let mut error_handler = ErrorHandler::new();
+        error_handler.lint(Lint::UnusedVariables, String::from("unused `a`"), 1);
+        error_handler.lint(Lint::Shadowing, String::from("`b` shadows"), 2);
+        assert!(error_handler.render_errors("").is_empty());
+
+        let mut lints = LintConfig::new();
+        lints.apply_flag("-W", "unused-variables").unwrap();
+        error_handler.set_lints(lints);
+        error_handler.lint(Lint::UnusedVariables, String::from("unused `a`"), 1);
+        assert!(!error_handler.has_error());
+        assert_eq!(
+            vec!["[line 1] Warning: unused `a`"],
+            error_handler.render_errors("")
+        );
+        assert_eq!(
+            Some((Lint::UnusedVariables, Level::Warn)),
+            error_handler.diagnostics()[0].lint
+        );
+
+        let mut lints = LintConfig::new();
+        lints.apply_flag("-D", "shadowing").unwrap();
+        error_handler.set_lints(lints);
+        error_handler.lint(Lint::Shadowing, String::from("`b` shadows"), 2);
+        assert!(error_handler.has_error());
+        assert_eq!(
+            vec![ErrorCode::UnusedVariable, ErrorCode::Shadowing],
+            error_handler.codes()
+        );
+    }
+
+    #[test]
+    fn test_human_format() {
+        let source_code = "var a = 1;\nvar b = a $ 2;\n";
+        let mut error_handler = ErrorHandler::new();
+        error_handler.set_format(ErrorFormat::Human);
+        error_handler.set_source_name(String::from("main.lox"));
+        error_handler.report_unexpected_char('$', 2, 11);
+        assert_eq!(
+            vec![
+                "error[L0001]: Unexpected character: $",
+                " --> main.lox:2:11",
+                "  |",
+                "2 | var b = a $ 2;",
+                "  |           ^",
+                "  = note: run `explain L0001` for more information",
+            ],
+            error_handler.render_errors(source_code)
+        );
+    }
+
+    #[test]
+    fn test_short_format_source_name() {
+        let mut error_handler = ErrorHandler::new();
+        error_handler.set_source_name(String::from("<stdin>"));
+        error_handler.report_unexpected_char('$', 1, 1);
+        assert_eq!(
+            vec!["[line 1] Error: Unexpected character: $"],
+            error_handler.render_errors("$")
+        );
+        error_handler.set_name_in_short_format(true);
+        assert_eq!(
+            vec!["<stdin>: [line 1] Error: Unexpected character: $"],
+            error_handler.render_errors("$")
+        );
+    }
+
+    #[test]
+    fn test_max_errors() {
+        let mut error_handler = ErrorHandler::with_max_errors(Some(2));
+        for line in 1..=5 {
+            error_handler.report_unexpected_char('$', line, 1);
+        }
+        assert_eq!(
+            vec![
+                "[line 1] Error: Unexpected character: $",
+                "[line 2] Error: Unexpected character: $",
+                "3 more errors suppressed",
+            ],
+            error_handler.render_errors("")
+        );
+        assert!(error_handler.has_error());
     }
 }
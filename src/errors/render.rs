@@ -0,0 +1,160 @@
+use std::fmt;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use super::{Diagnostic, Severity};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum ErrorFormat {
+    // `[line N] Error: message`, the format the CodeCrafters tests expect,
+    // prefixed with the source name for stdin and `-e` code
+    #[default]
+    Short,
+    // rustc-like output with the error code, location and a source excerpt
+    Human,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum ColorChoice {
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl FromStr for ErrorFormat {
+    type Err = String;
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "short" => Ok(ErrorFormat::Short),
+            "human" => Ok(ErrorFormat::Human),
+            other => Err(format!(
+                "unknown error format: {} (expected short or human)",
+                other
+            )),
+        }
+    }
+}
+
+impl Display for ErrorFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorFormat::Short => write!(f, "short"),
+            ErrorFormat::Human => write!(f, "human"),
+        }
+    }
+}
+
+impl FromStr for ColorChoice {
+    type Err = String;
+    fn from_str(choice: &str) -> Result<Self, Self::Err> {
+        match choice {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            other => Err(format!(
+                "unknown color choice: {} (expected auto, always or never)",
+                other
+            )),
+        }
+    }
+}
+
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const BLUE: &str = "\x1b[1;34m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+struct Painter {
+    color: bool,
+}
+
+impl Painter {
+    fn paint(&self, style: &str, text: &str) -> String {
+        if self.color {
+            format!("{}{}{}", style, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+// Only the diagnostic's own line: labels, lint notes and help are left to the
+// human format, so the output stays exactly what the tests expect.
+pub(super) fn render_short(err: &Diagnostic, source_name: Option<&str>, lines: &mut Vec<String>) {
+    match source_name {
+        Some(name) => lines.push(format!("{}: {}", name, err)),
+        None => lines.push(err.to_string()),
+    }
+}
+
+pub(super) fn render_human(
+    err: &Diagnostic,
+    source_name: &str,
+    source_code: &str,
+    color: bool,
+    lines: &mut Vec<String>,
+) {
+    let painter = Painter { color };
+    let (label, style) = match err.severity {
+        Severity::Error => ("error", RED),
+        Severity::Warning => ("warning", YELLOW),
+    };
+    lines.push(format!(
+        "{}{}",
+        painter.paint(style, &format!("{}[{}]", label, err.code)),
+        painter.paint(BOLD, &format!(": {}", err.error_msg))
+    ));
+
+    let gutter = " ".repeat(err.line_num.to_string().len());
+    let location = match err.columns {
+        Some((start, _)) => format!("{}:{}:{}", source_name, err.line_num, start),
+        None => format!("{}:{}", source_name, err.line_num),
+    };
+    lines.push(format!(
+        "{}{} {}",
+        gutter,
+        painter.paint(BLUE, "-->"),
+        location
+    ));
+
+    if let Some(source_line) = source_code.lines().nth(err.line_num.wrapping_sub(1)) {
+        let bar = painter.paint(BLUE, "|");
+        lines.push(format!("{} {}", gutter, bar));
+        lines.push(format!(
+            "{} {} {}",
+            painter.paint(BLUE, &err.line_num.to_string()),
+            bar,
+            source_line
+        ));
+        if let Some((start, end)) = err.columns {
+            let mut carets = "^".repeat(end.saturating_sub(start).max(1));
+            if let Some(label) = &err.label {
+                carets = format!("{} {}", carets, label);
+            }
+            lines.push(format!(
+                "{} {} {}{}",
+                gutter,
+                bar,
+                " ".repeat(start.saturating_sub(1)),
+                painter.paint(style, &carets)
+            ));
+        }
+    }
+
+    let equals = painter.paint(BLUE, "=");
+    if let Some((lint, level)) = err.lint {
+        lines.push(format!(
+            "{} {} note: lint `{}` is set to {}",
+            gutter, equals, lint, level
+        ));
+    }
+    for note in &err.help {
+        lines.push(format!("{} {} help: {}", gutter, equals, note));
+    }
+    lines.push(format!(
+        "{} {} note: run `explain {}` for more information",
+        gutter, equals, err.code
+    ));
+}
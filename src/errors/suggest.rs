@@ -0,0 +1,77 @@
+// Optimal string alignment distance: Levenshtein plus adjacent
+// transpositions, so `retrun` is a single edit away from `return`.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut dist = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in dist.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in dist[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            dist[i][j] = (dist[i - 1][j] + 1)
+                .min(dist[i][j - 1] + 1)
+                .min(dist[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                dist[i][j] = dist[i][j].min(dist[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    dist[a.len()][b.len()]
+}
+
+// Returns the closest candidate that is near enough to be a plausible typo of
+// `name`. Ties go to the candidate that comes first.
+pub fn suggest<'c, I>(name: &str, candidates: I) -> Option<&'c str>
+where
+    I: IntoIterator<Item = &'c str>,
+{
+    let max_distance = (name.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+pub fn did_you_mean(suggestion: &str) -> String {
+    format!("did you mean `{}`?", suggestion)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(0, edit_distance("fun", "fun"));
+        assert_eq!(1, edit_distance("fucn", "fun"));
+        assert_eq!(1, edit_distance("retrun", "return"));
+        assert_eq!(3, edit_distance("", "var"));
+        assert_eq!(3, edit_distance("kitten", "sitting"));
+    }
+
+    #[test]
+    fn test_suggest_keyword() {
+        let keywords = crate::token::keywords;
+        assert_eq!(Some("fun"), suggest("fucn", keywords()));
+        assert_eq!(Some("return"), suggest("retrun", keywords()));
+        assert_eq!(Some("while"), suggest("whiel", keywords()));
+        assert_eq!(Some("class"), suggest("clas", keywords()));
+        assert_eq!(None, suggest("print", keywords()));
+        assert_eq!(None, suggest("counter", keywords()));
+    }
+
+    #[test]
+    fn test_suggest_names() {
+        let names = ["count", "total", "index"];
+        assert_eq!(Some("count"), suggest("cout", names));
+        assert_eq!(None, suggest("x", names));
+    }
+}
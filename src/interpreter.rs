@@ -0,0 +1,994 @@
+pub mod class;
+pub mod environment;
+pub mod function;
+pub mod native;
+pub mod value;
+
+use std::cell::RefCell;
+use std::fmt;
+use std::fmt::Display;
+use std::io::{self, Write};
+use std::rc::Rc;
+use std::thread;
+
+use crate::ast::visitor::{ExprVisitor, StmtVisitor};
+use crate::ast::{Ast, ExprKind, FunctionDecl, LiteralValue, NodeId, NodeMap};
+use crate::errors::suggest::{did_you_mean, suggest};
+use crate::token::{Token, TokenType};
+use class::LoxClass;
+use environment::Environment;
+use function::LoxFunction;
+use native::NativeFunction;
+use value::{Callable, Value};
+
+// An error that stops the program while it runs, printed the way the book's
+// jlox prints it: the message, then the line on a line of its own.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RuntimeError {
+    pub message: String,
+    pub line: usize,
+    // the calls that were running when the error happened, innermost first
+    pub trace: Vec<Frame>,
+    pub help: Vec<String>,
+}
+
+// A call in progress: the function called and the line it was called from.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Frame {
+    pub function: String,
+    pub line: usize,
+}
+
+// Deep recursion would otherwise print hundreds of identical lines.
+const MAX_TRACEBACK_FRAMES: usize = 16;
+
+impl RuntimeError {
+    pub fn new<S: Into<String>>(token: &Token, message: S) -> Self {
+        RuntimeError {
+            message: message.into(),
+            line: token.line,
+            trace: Vec::new(),
+            help: Vec::new(),
+        }
+    }
+
+    // Where each running call was when the error happened, innermost first,
+    // in the style of clox. Empty for errors in top-level code.
+    pub fn traceback(&self) -> Vec<String> {
+        if self.trace.is_empty() {
+            return Vec::new();
+        }
+        let mut lines = vec![String::from("stack traceback:")];
+        let mut line = self.line;
+        for frame in self.trace.iter().take(MAX_TRACEBACK_FRAMES) {
+            lines.push(format!("  [line {}] in {}()", line, frame.function));
+            line = frame.line;
+        }
+        match self.trace.len().saturating_sub(MAX_TRACEBACK_FRAMES) {
+            0 => lines.push(format!("  [line {}] in script", line)),
+            hidden => lines.push(format!("  ... {} more calls", hidden)),
+        }
+        lines
+    }
+}
+
+impl Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}\n[line {}]", self.message, self.line)
+    }
+}
+
+// Why execution stopped before reaching the end of the program.
+#[derive(Debug)]
+pub enum Unwind {
+    Error(RuntimeError),
+    // `print` could not write to the output
+    Io(io::Error),
+    // a `return` statement on its way out to the call it returns from
+    Return(Value),
+}
+
+impl From<RuntimeError> for Unwind {
+    fn from(error: RuntimeError) -> Self {
+        Unwind::Error(error)
+    }
+}
+
+impl From<io::Error> for Unwind {
+    fn from(error: io::Error) -> Self {
+        Unwind::Io(error)
+    }
+}
+
+impl Display for Unwind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Unwind::Error(error) => write!(f, "{}", error),
+            Unwind::Io(error) => write!(f, "{}", error),
+            Unwind::Return(value) => write!(f, "return {}", value),
+        }
+    }
+}
+
+// Lox calls nest on the Rust stack, so the interpreter runs on a thread with
+// this much of it; see `with_stack`.
+pub const STACK_SIZE: usize = 256 << 20;
+
+// A generous bound on the stack one Lox call takes in a debug build, about six
+// times what a simple recursive function needs.
+const CALL_STACK_SIZE: usize = 32 << 10;
+
+// How deep Lox calls may nest before the program is stopped, well before the
+// interpreter itself would run out of stack.
+const MAX_CALL_DEPTH: usize = STACK_SIZE / CALL_STACK_SIZE;
+
+// Runs `f` on a new thread with `STACK_SIZE` of stack, which the interpreter
+// needs to nest calls `MAX_CALL_DEPTH` deep, and waits for its result.
+pub fn with_stack<T: Send>(f: impl FnOnce() -> T + Send) -> io::Result<T> {
+    thread::scope(|scope| {
+        let handle = thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn_scoped(scope, f)?;
+        Ok(handle
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic)))
+    })
+}
+
+type EvalResult = Result<Value, Unwind>;
+type ExecResult = Result<(), Unwind>;
+
+pub struct Interpreter {
+    // the tree being run; functions carry the tree they were declared in
+    ast: Rc<Ast>,
+    globals: Rc<RefCell<Environment>>,
+    environment: Rc<RefCell<Environment>>,
+    // scope depths of local variable references, once the program has been
+    // through the resolver
+    locals: Option<NodeMap<usize>>,
+    out: Box<dyn Write>,
+    // the calls in progress, outermost first
+    frames: Vec<Frame>,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Interpreter::with_output(Box::new(io::stdout()))
+    }
+}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        Interpreter::default()
+    }
+
+    // `print` writes to `out` instead of standard output.
+    pub fn with_output(out: Box<dyn Write>) -> Self {
+        let globals = Environment::new();
+        let mut interpreter = Interpreter {
+            ast: Rc::new(Ast::new()),
+            environment: Rc::clone(&globals),
+            globals,
+            locals: None,
+            out,
+            frames: Vec::new(),
+        };
+        for native in native::registry() {
+            interpreter.define_native(native);
+        }
+        interpreter
+    }
+
+    pub fn define_native(&mut self, native: NativeFunction) {
+        self.globals
+            .borrow_mut()
+            .define(native.name, Value::Callable(Rc::new(native)));
+    }
+
+    // Without resolved locals every variable is looked up by name, innermost
+    // scope first.
+    pub fn set_locals(&mut self, locals: NodeMap<usize>) {
+        self.locals = Some(locals);
+    }
+
+    // Runs the statements of a parsed program. A `return` at the top level
+    // ends the program.
+    pub fn interpret(&mut self, ast: Ast) -> ExecResult {
+        self.ast = Rc::new(ast);
+        let ast = Rc::clone(&self.ast);
+        for statement in ast.roots() {
+            match self.execute(*statement) {
+                Err(Unwind::Return(_)) => break,
+                result => result?,
+            }
+        }
+        self.out.flush()?;
+        Ok(())
+    }
+
+    // The value of a parsed expression.
+    pub fn interpret_expression(&mut self, ast: Ast) -> EvalResult {
+        self.ast = Rc::new(ast);
+        self.evaluate(self.ast.root())
+    }
+
+    fn evaluate(&mut self, id: NodeId) -> EvalResult {
+        let ast = Rc::clone(&self.ast);
+        ast.expr(id).accept(self)
+    }
+
+    fn execute(&mut self, id: NodeId) -> ExecResult {
+        let ast = Rc::clone(&self.ast);
+        ast.stmt(id).accept(self)
+    }
+
+    // The environment to look a variable up in, and how many scopes out from
+    // it the variable is when the resolver knows.
+    fn scope_of(&self, id: NodeId) -> (&Rc<RefCell<Environment>>, Option<usize>) {
+        match &self.locals {
+            Some(locals) => match locals.get(id) {
+                Some(depth) => (&self.environment, Some(*depth)),
+                None => (&self.globals, None),
+            },
+            None => (&self.environment, None),
+        }
+    }
+
+    fn look_up_variable(&self, id: NodeId, name: &Token) -> EvalResult {
+        let value = match self.scope_of(id) {
+            (scope, Some(depth)) => environment::get_at(scope, depth, name),
+            (scope, None) => scope.borrow().get(name),
+        };
+        Ok(value.map_err(|error| self.suggest_name(error, name))?)
+    }
+
+    // Adds a "did you mean" to an undefined variable error, from the names
+    // visible where the variable was used.
+    fn suggest_name(&self, mut error: RuntimeError, name: &Token) -> RuntimeError {
+        let names = self.environment.borrow().names();
+        if let Some(suggestion) = suggest(&name.lexeme, names.iter().map(String::as_str)) {
+            error.help.push(did_you_mean(suggestion));
+        }
+        error
+    }
+
+    // Runs `statements` in `environment`, restoring the current scope
+    // afterwards even if they fail.
+    pub fn execute_block(
+        &mut self,
+        statements: &[NodeId],
+        environment: Rc<RefCell<Environment>>,
+    ) -> ExecResult {
+        let previous = std::mem::replace(&mut self.environment, environment);
+        let result = statements.iter().try_for_each(|stmt| self.execute(*stmt));
+        self.environment = previous;
+        result
+    }
+
+    // Runs the body of a function, whose statements are in `ast`.
+    pub fn execute_body(
+        &mut self,
+        ast: &Rc<Ast>,
+        body: &[NodeId],
+        environment: Rc<RefCell<Environment>>,
+    ) -> ExecResult {
+        let previous = std::mem::replace(&mut self.ast, Rc::clone(ast));
+        let result = self.execute_block(body, environment);
+        self.ast = previous;
+        result
+    }
+}
+
+fn number_operand(operator: &Token, operand: &Value) -> Result<f64, RuntimeError> {
+    match operand {
+        Value::Number(num) => Ok(num.value()),
+        _ => Err(RuntimeError::new(operator, "Operand must be a number.")),
+    }
+}
+
+fn number_operands(
+    operator: &Token,
+    left: &Value,
+    right: &Value,
+) -> Result<(f64, f64), RuntimeError> {
+    match (left, right) {
+        (Value::Number(left), Value::Number(right)) => Ok((left.value(), right.value())),
+        _ => Err(RuntimeError::new(operator, "Operands must be numbers.")),
+    }
+}
+
+impl ExprVisitor<EvalResult> for Interpreter {
+    fn visit_literal(&mut self, _: NodeId, value: &LiteralValue) -> EvalResult {
+        Ok(match value {
+            LiteralValue::Number(num) => Value::Number(num.clone()),
+            LiteralValue::String(value) => Value::String(value.clone()),
+            LiteralValue::Bool(value) => Value::Bool(*value),
+            LiteralValue::Nil => Value::Nil,
+        })
+    }
+
+    fn visit_grouping(&mut self, _: NodeId, expr: NodeId) -> EvalResult {
+        self.evaluate(expr)
+    }
+
+    fn visit_unary(&mut self, _: NodeId, operator: &Token, right: NodeId) -> EvalResult {
+        let right = self.evaluate(right)?;
+        match operator.token_type {
+            TokenType::Minus => Ok(Value::number(-number_operand(operator, &right)?)),
+            TokenType::Bang => Ok(Value::Bool(!right.is_truthy())),
+            _ => unreachable!("the parser only builds unary `-` and `!`"),
+        }
+    }
+
+    fn visit_binary(
+        &mut self,
+        _: NodeId,
+        left: NodeId,
+        operator: &Token,
+        right: NodeId,
+    ) -> EvalResult {
+        let left = self.evaluate(left)?;
+        let right = self.evaluate(right)?;
+        let value = match operator.token_type {
+            TokenType::EqualEqual => Value::Bool(left == right),
+            TokenType::BangEqual => Value::Bool(left != right),
+            TokenType::Plus => match (&left, &right) {
+                (Value::Number(a), Value::Number(b)) => Value::number(a.value() + b.value()),
+                (Value::String(a), Value::String(b)) => Value::String(format!("{}{}", a, b)),
+                _ => {
+                    return Err(RuntimeError::new(
+                        operator,
+                        "Operands must be two numbers or two strings.",
+                    )
+                    .into())
+                }
+            },
+            _ => {
+                let (a, b) = number_operands(operator, &left, &right)?;
+                match operator.token_type {
+                    TokenType::Minus => Value::number(a - b),
+                    TokenType::Star => Value::number(a * b),
+                    TokenType::Slash => Value::number(a / b),
+                    TokenType::Percent => Value::number(a % b),
+                    TokenType::StarStar => Value::number(a.powf(b)),
+                    TokenType::Greater => Value::Bool(a > b),
+                    TokenType::GreaterEqual => Value::Bool(a >= b),
+                    TokenType::Less => Value::Bool(a < b),
+                    TokenType::LessEqual => Value::Bool(a <= b),
+                    _ => unreachable!("not a binary operator: {:?}", operator.token_type),
+                }
+            }
+        };
+        Ok(value)
+    }
+
+    // Returns whichever operand decided the result, not a boolean.
+    fn visit_logical(
+        &mut self,
+        _: NodeId,
+        left: NodeId,
+        operator: &Token,
+        right: NodeId,
+    ) -> EvalResult {
+        let left = self.evaluate(left)?;
+        let short_circuits = match operator.token_type {
+            TokenType::Or => left.is_truthy(),
+            _ => !left.is_truthy(),
+        };
+        if short_circuits {
+            Ok(left)
+        } else {
+            self.evaluate(right)
+        }
+    }
+
+    fn visit_conditional(
+        &mut self,
+        _: NodeId,
+        condition: NodeId,
+        then_branch: NodeId,
+        else_branch: NodeId,
+    ) -> EvalResult {
+        if self.evaluate(condition)?.is_truthy() {
+            self.evaluate(then_branch)
+        } else {
+            self.evaluate(else_branch)
+        }
+    }
+
+    fn visit_variable(&mut self, id: NodeId, name: &Token) -> EvalResult {
+        self.look_up_variable(id, name)
+    }
+
+    fn visit_assign(&mut self, id: NodeId, name: &Token, value: NodeId) -> EvalResult {
+        let value = self.evaluate(value)?;
+        let assigned = match self.scope_of(id) {
+            (scope, Some(depth)) => environment::assign_at(scope, depth, name, value.clone()),
+            (scope, None) => scope.borrow_mut().assign(name, value.clone()),
+        };
+        assigned.map_err(|error| self.suggest_name(error, name))?;
+        Ok(value)
+    }
+
+    fn visit_call(
+        &mut self,
+        _: NodeId,
+        callee: NodeId,
+        paren: &Token,
+        arguments: &[NodeId],
+    ) -> EvalResult {
+        let callee = self.evaluate(callee)?;
+        let arguments = arguments
+            .iter()
+            .map(|argument| self.evaluate(*argument))
+            .collect::<Result<Vec<Value>, Unwind>>()?;
+        let callable: Rc<dyn Callable> = match callee {
+            Value::Callable(callable) => callable,
+            Value::Class(class) => class,
+            _ => {
+                return Err(RuntimeError::new(paren, "Can only call functions and classes.").into())
+            }
+        };
+        if arguments.len() != callable.arity() {
+            return Err(RuntimeError::new(
+                paren,
+                format!(
+                    "Expected {} arguments but got {}.",
+                    callable.arity(),
+                    arguments.len()
+                ),
+            )
+            .into());
+        }
+        if self.frames.len() == MAX_CALL_DEPTH {
+            return Err(RuntimeError::new(paren, "Stack overflow.").into());
+        }
+        self.frames.push(Frame {
+            function: callable.name().to_string(),
+            line: paren.line,
+        });
+        let mut result = callable.call(self, arguments);
+        let frame = self.frames.pop();
+        // Each call adds itself on the way out, so the trace ends up
+        // innermost first.
+        if let (Err(Unwind::Error(error)), Some(frame)) = (&mut result, frame) {
+            error.trace.push(frame);
+        }
+        result
+    }
+
+    fn visit_get(&mut self, _: NodeId, object: NodeId, name: &Token) -> EvalResult {
+        let Value::Instance(instance) = self.evaluate(object)? else {
+            return Err(RuntimeError::new(name, "Only instances have properties.").into());
+        };
+        // Fields shadow methods.
+        if let Some(value) = instance.fields.borrow().get(&name.lexeme) {
+            return Ok(value.clone());
+        }
+        match instance.class.find_method(&name.lexeme) {
+            Some(method) => Ok(Value::Callable(Rc::new(method.bind(Rc::clone(&instance))))),
+            None => Err(
+                RuntimeError::new(name, format!("Undefined property '{}'.", name.lexeme)).into(),
+            ),
+        }
+    }
+
+    fn visit_set(&mut self, _: NodeId, object: NodeId, name: &Token, value: NodeId) -> EvalResult {
+        let Value::Instance(instance) = self.evaluate(object)? else {
+            return Err(RuntimeError::new(name, "Only instances have fields.").into());
+        };
+        let value = self.evaluate(value)?;
+        instance
+            .fields
+            .borrow_mut()
+            .insert(name.lexeme.clone(), value.clone());
+        Ok(value)
+    }
+
+    fn visit_this(&mut self, id: NodeId, keyword: &Token) -> EvalResult {
+        self.look_up_variable(id, keyword)
+    }
+
+    // `super` is bound one scope out from `this`, around the methods of a
+    // subclass.
+    fn visit_super(&mut self, id: NodeId, keyword: &Token, method: &Token) -> EvalResult {
+        let this = Token {
+            token_type: TokenType::This,
+            lexeme: String::from("this"),
+            ..keyword.clone()
+        };
+        let (superclass, object) = match self.scope_of(id) {
+            (scope, Some(depth)) => (
+                environment::get_at(scope, depth, keyword)?,
+                environment::get_at(scope, depth.saturating_sub(1), &this)?,
+            ),
+            (scope, None) => (scope.borrow().get(keyword)?, scope.borrow().get(&this)?),
+        };
+        let (Value::Class(superclass), Value::Instance(object)) = (superclass, object) else {
+            unreachable!("`super` and `this` are bound by the class declaration");
+        };
+        match superclass.find_method(&method.lexeme) {
+            Some(method) => Ok(Value::Callable(Rc::new(method.bind(object)))),
+            None => Err(RuntimeError::new(
+                method,
+                format!("Undefined property '{}'.", method.lexeme),
+            )
+            .into()),
+        }
+    }
+}
+
+impl StmtVisitor<ExecResult> for Interpreter {
+    fn visit_expression_stmt(&mut self, expr: NodeId) -> ExecResult {
+        self.evaluate(expr)?;
+        Ok(())
+    }
+
+    fn visit_print(&mut self, expr: NodeId) -> ExecResult {
+        let value = self.evaluate(expr)?;
+        writeln!(self.out, "{}", value)?;
+        Ok(())
+    }
+
+    fn visit_var(&mut self, name: &Token, initializer: Option<NodeId>) -> ExecResult {
+        let value = match initializer {
+            Some(initializer) => self.evaluate(initializer)?,
+            None => Value::Nil,
+        };
+        self.environment
+            .borrow_mut()
+            .define(name.lexeme.clone(), value);
+        Ok(())
+    }
+
+    fn visit_block(&mut self, statements: &[NodeId]) -> ExecResult {
+        let environment = Environment::with_enclosing(Rc::clone(&self.environment));
+        self.execute_block(statements, environment)
+    }
+
+    fn visit_if(
+        &mut self,
+        condition: NodeId,
+        then_branch: NodeId,
+        else_branch: Option<NodeId>,
+    ) -> ExecResult {
+        if self.evaluate(condition)?.is_truthy() {
+            self.execute(then_branch)
+        } else if let Some(else_branch) = else_branch {
+            self.execute(else_branch)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn visit_while(&mut self, condition: NodeId, body: NodeId) -> ExecResult {
+        while self.evaluate(condition)?.is_truthy() {
+            self.execute(body)?;
+        }
+        Ok(())
+    }
+
+    fn visit_function(&mut self, function: &Rc<FunctionDecl>) -> ExecResult {
+        let closure = Rc::clone(&self.environment);
+        let function = LoxFunction::new(Rc::clone(function), Rc::clone(&self.ast), closure, false);
+        let name = function.name().to_string();
+        self.environment
+            .borrow_mut()
+            .define(name, Value::Callable(Rc::new(function)));
+        Ok(())
+    }
+
+    fn visit_return(&mut self, _: &Token, value: Option<NodeId>) -> ExecResult {
+        let value = match value {
+            Some(value) => self.evaluate(value)?,
+            None => Value::Nil,
+        };
+        Err(Unwind::Return(value))
+    }
+
+    fn visit_class(
+        &mut self,
+        name: &Token,
+        superclass: Option<NodeId>,
+        methods: &[Rc<FunctionDecl>],
+    ) -> ExecResult {
+        let superclass = match superclass {
+            Some(superclass) => match self.evaluate(superclass)? {
+                Value::Class(class) => Some(class),
+                _ => {
+                    let token = match &self.ast.expr(superclass).kind {
+                        ExprKind::Variable { name } => name,
+                        _ => name,
+                    };
+                    return Err(RuntimeError::new(token, "Superclass must be a class.").into());
+                }
+            },
+            None => None,
+        };
+        let enclosing = Rc::clone(&self.environment);
+        if let Some(superclass) = &superclass {
+            self.environment = Environment::with_enclosing(Rc::clone(&enclosing));
+            self.environment
+                .borrow_mut()
+                .define("super", Value::Class(Rc::clone(superclass)));
+        }
+        let methods = methods
+            .iter()
+            .map(|method| {
+                let function = LoxFunction::new(
+                    Rc::clone(method),
+                    Rc::clone(&self.ast),
+                    Rc::clone(&self.environment),
+                    method.name.lexeme == "init",
+                );
+                (method.name.lexeme.clone(), Rc::new(function))
+            })
+            .collect();
+        let class = LoxClass::new(name.lexeme.clone(), superclass, methods);
+        self.environment = enclosing;
+        self.environment
+            .borrow_mut()
+            .define(name.lexeme.clone(), Value::Class(Rc::new(class)));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::token::Dialect;
+    use crate::{parse_expression_with, parse_program_with, resolve_with, Options};
+
+    // Collects what `print` writes so tests can look at it afterwards.
+    #[derive(Clone, Default)]
+    struct Output(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Output {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn options() -> Options {
+        Options {
+            dialect: Dialect::Extended,
+            ..Options::default()
+        }
+    }
+
+    fn evaluate(source_code: &str) -> EvalResult {
+        let expr = parse_expression_with(source_code, &options()).ast.unwrap();
+        Interpreter::new().interpret_expression(expr)
+    }
+
+    // Runs a program and returns its output, followed by the runtime error if
+    // it stopped on one.
+    fn run(source_code: &str) -> String {
+        let statements = parse_program_with(source_code, &options()).ast.unwrap();
+        let resolved = resolve_with(&statements, &options());
+        assert!(!resolved.has_errors(), "{:?}", resolved.diagnostics);
+        let output = Output::default();
+        let mut interpreter = Interpreter::with_output(Box::new(output.clone()));
+        interpreter.set_locals(resolved.locals);
+        let result = interpreter.interpret(statements);
+        let mut text = String::from_utf8(output.0.take()).unwrap();
+        if let Err(error) = result {
+            text.push_str(&error.to_string());
+        }
+        text
+    }
+
+    fn show(source_code: &str) -> String {
+        evaluate(source_code).unwrap().to_string()
+    }
+
+    fn error(source_code: &str) -> String {
+        evaluate(source_code).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert_eq!("14", show("2 + 3 * 4"));
+        assert_eq!("-1.5", show("-(3 / 2)"));
+        assert_eq!("1", show("7 % 3"));
+        assert_eq!("512", show("2 ** 3 ** 2"));
+        assert_eq!("hello world", show("\"hello\" + \" \" + \"world\""));
+    }
+
+    #[test]
+    fn test_comparison_and_equality() {
+        assert_eq!("true", show("1 < 2 == !false"));
+        assert_eq!("false", show("\"1\" == 1"));
+        assert_eq!("true", show("nil == nil"));
+        assert_eq!("true", show("\"a\" != \"b\""));
+    }
+
+    #[test]
+    fn test_logic() {
+        assert_eq!("hi", show("nil or \"hi\""));
+        assert_eq!("nil", show("nil and undefined"));
+        assert_eq!("2", show("false ? 1 : 2"));
+        assert_eq!("false", show("!!nil"));
+    }
+
+    #[test]
+    fn test_runtime_errors() {
+        assert_eq!("Operand must be a number.\n[line 1]", error("-\"a\""));
+        assert_eq!("Operands must be numbers.\n[line 1]", error("1 < true"));
+        assert_eq!(
+            "Operands must be two numbers or two strings.\n[line 2]",
+            error("1\n + \"a\"")
+        );
+        assert_eq!("Undefined variable 'x'.\n[line 1]", error("x"));
+        assert_eq!(
+            "Can only call functions and classes.\n[line 1]",
+            error("\"f\"()")
+        );
+        assert_eq!("Only instances have properties.\n[line 1]", error("true.x"));
+    }
+
+    #[test]
+    fn test_variables_and_scopes() {
+        assert_eq!("3\n", run("var a = 1; var b = 2; print a + b;"));
+        assert_eq!("nil\n", run("var a; print a;"));
+        assert_eq!("2\n", run("var a = 1; a = 2; print a;"));
+        assert_eq!(
+            "inner\nouter\n",
+            run("var a = \"outer\"; { var a = \"inner\"; print a; } print a;")
+        );
+        assert_eq!("2\n", run("var a = 1; { a = 2; } print a;"));
+        assert_eq!(
+            "Undefined variable 'b'.\n[line 1]",
+            run("{ var b = 1; } print b;")
+        );
+        assert_eq!("Undefined variable 'c'.\n[line 2]", run("var a;\nc = 1;"));
+    }
+
+    #[test]
+    fn test_undefined_variable_suggestions() {
+        let help = |source_code: &str| -> Vec<String> {
+            let statements = parse_program_with(source_code, &options()).ast.unwrap();
+            let resolved = resolve_with(&statements, &options());
+            let mut interpreter = Interpreter::with_output(Box::new(Output::default()));
+            interpreter.set_locals(resolved.locals);
+            match interpreter.interpret(statements) {
+                Err(Unwind::Error(error)) => error.help,
+                _ => panic!("expected a runtime error"),
+            }
+        };
+        assert_eq!(
+            vec!["did you mean `count`?"],
+            help("fun f() { var count = 1; print cout; } f();")
+        );
+        assert_eq!(vec!["did you mean `total`?"], help("var total; totl = 1;"));
+        assert_eq!(vec!["did you mean `clock`?"], help("print clokc();"));
+        assert!(help("print x;").is_empty());
+    }
+
+    #[test]
+    fn test_control_flow() {
+        assert_eq!("yes\n", run("if (1 < 2) print \"yes\"; else print \"no\";"));
+        assert_eq!("no\n", run("if (nil) print \"yes\"; else print \"no\";"));
+        assert_eq!(
+            "0\n1\n2\n",
+            run("var i = 0; while (i < 3) { print i; i = i + 1; }")
+        );
+        assert_eq!("0\n1\n", run("for (var i = 0; i < 2; i = i + 1) print i;"));
+        assert_eq!(
+            "1\nOperand must be a number.\n[line 2]",
+            run("print 1;\nprint -nil;\nprint 2;")
+        );
+    }
+
+    #[test]
+    fn test_functions() {
+        assert_eq!(
+            "Hi, Bob!\nnil\n",
+            run("fun greet(name) { print \"Hi, \" + name + \"!\"; } print greet(\"Bob\");")
+        );
+        assert_eq!(
+            "55\n",
+            run("fun fib(n) { if (n < 2) return n; return fib(n - 1) + fib(n - 2); } print fib(10);")
+        );
+        assert_eq!(
+            "<fn add>\n3\n",
+            run("fun add(a, b) { return a + b; } var f = add; print f; print f(1, 2);")
+        );
+        assert_eq!(
+            "Expected 2 arguments but got 1.\n[line 1]",
+            run("fun f(a, b) {} f(1);")
+        );
+    }
+
+    #[test]
+    fn test_deep_recursion() {
+        let deep = |depth: usize| {
+            with_stack(move || {
+                run(&format!(
+                    "fun f(n) {{ if (n == 0) return 0; return f(n - 1) + 1; }} print f({});",
+                    depth
+                ))
+            })
+            .unwrap()
+        };
+        assert_eq!("1000\n", deep(1000));
+        assert_eq!(
+            format!("{}\n", MAX_CALL_DEPTH - 1),
+            deep(MAX_CALL_DEPTH - 1)
+        );
+        assert_eq!("Stack overflow.\n[line 1]", deep(MAX_CALL_DEPTH));
+        assert_eq!(
+            "Stack overflow.\n[line 1]",
+            with_stack(|| run("fun f() { return f(); } f();")).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_closures() {
+        assert_eq!(
+            "1\n2\n1\n",
+            run(
+                "fun counter() { var i = 0; fun count() { i = i + 1; return i; } return count; }
+                 var a = counter(); var b = counter(); print a(); print a(); print b();"
+            )
+        );
+    }
+
+    #[test]
+    fn test_closures_bind_lexically() {
+        assert_eq!(
+            "global\nglobal\n",
+            run("var a = \"global\";
+                 { fun show() { print a; } show(); var a = \"block\"; show(); }")
+        );
+    }
+
+    #[test]
+    fn test_natives() {
+        assert_eq!(
+            "<native fn>\ntrue\n",
+            run("print clock; print clock() > 0;")
+        );
+
+        fn twice(arguments: &[Value]) -> Value {
+            match &arguments[0] {
+                Value::Number(num) => Value::number(num.value() * 2.0),
+                _ => Value::Nil,
+            }
+        }
+        let statements = parse_program_with("print twice(21);", &options())
+            .ast
+            .unwrap();
+        let output = Output::default();
+        let mut interpreter = Interpreter::with_output(Box::new(output.clone()));
+        interpreter.define_native(NativeFunction {
+            name: "twice",
+            arity: 1,
+            function: twice,
+        });
+        interpreter.interpret(statements).unwrap();
+        assert_eq!(b"42\n", output.0.take().as_slice());
+    }
+
+    #[test]
+    fn test_classes() {
+        assert_eq!(
+            "Point\nPoint instance\n3\n",
+            run("class Point {} var p = Point(); print Point; print p; p.x = 3; print p.x;")
+        );
+        assert_eq!(
+            "Hi, Ann\n",
+            run("class Person { init(name) { this.name = name; }
+                   greet() { print \"Hi, \" + this.name; } }
+                 var greet = Person(\"Ann\").greet; greet();")
+        );
+        assert_eq!(
+            "true\n",
+            run("class A { init() { this.n = 1; return; } } var a = A(); print a.init() == a;")
+        );
+        assert_eq!(
+            "field\n",
+            run("class A { m() { return \"method\"; } } var a = A(); a.m = \"field\"; print a.m;")
+        );
+        assert_eq!(
+            "Undefined property 'y'.\n[line 1]",
+            run("class A {} A().y;")
+        );
+        assert_eq!(
+            "Expected 1 arguments but got 0.\n[line 1]",
+            run("class A { init(a) {} } A();")
+        );
+        assert_eq!(
+            "Only instances have fields.\n[line 1]",
+            run("class A {} A.x = 1;")
+        );
+    }
+
+    #[test]
+    fn test_inheritance() {
+        assert_eq!(
+            "Fry until golden brown.\nPipe full of custard and coat with chocolate.\n",
+            run("class Doughnut { cook() { print \"Fry until golden brown.\"; } }
+                 class BostonCream < Doughnut {
+                   cook() { super.cook(); print \"Pipe full of custard and coat with chocolate.\"; } }
+                 BostonCream().cook();")
+        );
+        assert_eq!(
+            "A method\nB\n",
+            run(
+                "class A { method() { print \"A method\"; } init(name) { this.name = name; } }
+                 class B < A {} class C < B {}
+                 var c = C(\"B\"); c.method(); print c.name;"
+            )
+        );
+        // `super` is looked up from the class the method is declared in,
+        // not the class of the instance.
+        assert_eq!(
+            "A method\n",
+            run("class A { method() { print \"A method\"; } }
+                 class B < A { method() { print \"B method\"; } test() { super.method(); } }
+                 class C < B {} C().test();")
+        );
+        assert_eq!(
+            "Superclass must be a class.\n[line 1]",
+            run("var NotAClass = 1; class A < NotAClass {}")
+        );
+        assert_eq!(
+            "Undefined property 'missing'.\n[line 1]",
+            run("class A {} class B < A { f() { return super.missing; } } B().f();")
+        );
+    }
+
+    #[test]
+    fn test_traceback() {
+        let statements = parse_program_with(
+            "fun inner() {\n  return 1 + nil;\n}\nfun outer() {\n  inner();\n}\nouter();",
+            &options(),
+        )
+        .ast
+        .unwrap();
+        let mut interpreter = Interpreter::with_output(Box::new(Output::default()));
+        let Err(Unwind::Error(error)) = interpreter.interpret(statements) else {
+            panic!("expected a runtime error");
+        };
+        assert_eq!(
+            "Operands must be two numbers or two strings.\n[line 2]",
+            error.to_string()
+        );
+        assert_eq!(
+            vec![
+                "stack traceback:",
+                "  [line 2] in inner()",
+                "  [line 5] in outer()",
+                "  [line 7] in script",
+            ],
+            error.traceback()
+        );
+        assert!(interpreter.frames.is_empty());
+
+        let traceback = with_stack(|| {
+            let statements = parse_program_with("fun f() { f(); }\nf();", &options())
+                .ast
+                .unwrap();
+            let mut interpreter = Interpreter::with_output(Box::new(Output::default()));
+            let Err(Unwind::Error(error)) = interpreter.interpret(statements) else {
+                panic!("expected a runtime error");
+            };
+            error.traceback()
+        })
+        .unwrap();
+        assert_eq!(MAX_TRACEBACK_FRAMES + 2, traceback.len());
+        assert_eq!(
+            format!("  ... {} more calls", MAX_CALL_DEPTH - MAX_TRACEBACK_FRAMES),
+            traceback[MAX_TRACEBACK_FRAMES + 1]
+        );
+        let Err(Unwind::Error(error)) = evaluate("-nil") else {
+            panic!("expected a runtime error");
+        };
+        assert!(error.traceback().is_empty());
+    }
+}
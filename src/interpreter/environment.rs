@@ -0,0 +1,115 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::value::Value;
+use super::RuntimeError;
+use crate::token::Token;
+
+// One scope's variables. Scopes are shared because closures keep the scope
+// they were created in alive after the block that made it has finished.
+#[derive(Debug, Default)]
+pub struct Environment {
+    values: HashMap<String, Value>,
+    enclosing: Option<Rc<RefCell<Environment>>>,
+}
+
+impl Environment {
+    pub fn new() -> Rc<RefCell<Environment>> {
+        Rc::new(RefCell::new(Environment::default()))
+    }
+
+    pub fn with_enclosing(enclosing: Rc<RefCell<Environment>>) -> Rc<RefCell<Environment>> {
+        Rc::new(RefCell::new(Environment {
+            values: HashMap::new(),
+            enclosing: Some(enclosing),
+        }))
+    }
+
+    // Redefining a name in the same scope replaces it, like the book does for
+    // globals.
+    pub fn define<S: Into<String>>(&mut self, name: S, value: Value) {
+        self.values.insert(name.into(), value);
+    }
+
+    // A variable of this scope only, without looking further out.
+    pub fn get_here(&self, name: &str) -> Option<Value> {
+        self.values.get(name).cloned()
+    }
+
+    // Every name visible from this scope, innermost first.
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.values.keys().cloned().collect();
+        names.sort();
+        if let Some(enclosing) = &self.enclosing {
+            names.extend(enclosing.borrow().names());
+        }
+        names
+    }
+
+    pub fn get(&self, name: &Token) -> Result<Value, RuntimeError> {
+        if let Some(value) = self.values.get(&name.lexeme) {
+            return Ok(value.clone());
+        }
+        match &self.enclosing {
+            Some(enclosing) => enclosing.borrow().get(name),
+            None => Err(undefined(name)),
+        }
+    }
+
+    pub fn assign(&mut self, name: &Token, value: Value) -> Result<(), RuntimeError> {
+        if let Some(slot) = self.values.get_mut(&name.lexeme) {
+            *slot = value;
+            return Ok(());
+        }
+        match &self.enclosing {
+            Some(enclosing) => enclosing.borrow_mut().assign(name, value),
+            None => Err(undefined(name)),
+        }
+    }
+}
+
+// The scope `depth` steps out from `environment`, as counted by the resolver.
+pub fn ancestor(environment: &Rc<RefCell<Environment>>, depth: usize) -> Rc<RefCell<Environment>> {
+    let mut environment = Rc::clone(environment);
+    for _ in 0..depth {
+        let enclosing = environment
+            .borrow()
+            .enclosing
+            .clone()
+            .expect("the resolver counted more scopes than there are");
+        environment = enclosing;
+    }
+    environment
+}
+
+pub fn get_at(
+    environment: &Rc<RefCell<Environment>>,
+    depth: usize,
+    name: &Token,
+) -> Result<Value, RuntimeError> {
+    let scope = ancestor(environment, depth);
+    let value = scope.borrow().get_here(&name.lexeme);
+    value.ok_or_else(|| undefined(name))
+}
+
+pub fn assign_at(
+    environment: &Rc<RefCell<Environment>>,
+    depth: usize,
+    name: &Token,
+    value: Value,
+) -> Result<(), RuntimeError> {
+    let scope = ancestor(environment, depth);
+    let mut scope = scope.borrow_mut();
+    match scope.values.get_mut(&name.lexeme) {
+        Some(slot) => {
+            *slot = value;
+            Ok(())
+        }
+        None => Err(undefined(name)),
+    }
+}
+
+fn undefined(name: &Token) -> RuntimeError {
+    RuntimeError::new(name, format!("Undefined variable '{}'.", name.lexeme))
+}
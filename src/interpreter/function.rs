@@ -0,0 +1,89 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::fmt::Display;
+use std::rc::Rc;
+
+use super::environment::Environment;
+use super::value::{Callable, Instance, Value};
+use super::{Interpreter, Unwind};
+use crate::ast::{Ast, FunctionDecl};
+
+// A function declared in Lox, together with the scope it was declared in.
+pub struct LoxFunction {
+    // Shared with the tree the declaration is in, which the function keeps
+    // alive to run its body.
+    declaration: Rc<FunctionDecl>,
+    ast: Rc<Ast>,
+    closure: Rc<RefCell<Environment>>,
+    // an `init` method, which always returns the instance it initialized
+    is_initializer: bool,
+}
+
+impl LoxFunction {
+    pub fn new(
+        declaration: Rc<FunctionDecl>,
+        ast: Rc<Ast>,
+        closure: Rc<RefCell<Environment>>,
+        is_initializer: bool,
+    ) -> Self {
+        LoxFunction {
+            declaration,
+            ast,
+            closure,
+            is_initializer,
+        }
+    }
+
+    // The method with `this` bound to `instance`.
+    pub fn bind(&self, instance: Rc<Instance>) -> LoxFunction {
+        let environment = Environment::with_enclosing(Rc::clone(&self.closure));
+        environment
+            .borrow_mut()
+            .define("this", Value::Instance(instance));
+        LoxFunction::new(
+            Rc::clone(&self.declaration),
+            Rc::clone(&self.ast),
+            environment,
+            self.is_initializer,
+        )
+    }
+
+    fn this(&self) -> Value {
+        self.closure.borrow().get_here("this").unwrap_or(Value::Nil)
+    }
+}
+
+impl Callable for LoxFunction {
+    fn name(&self) -> &str {
+        &self.declaration.name.lexeme
+    }
+
+    fn arity(&self) -> usize {
+        self.declaration.params.len()
+    }
+
+    fn call(
+        self: Rc<Self>,
+        interpreter: &mut Interpreter,
+        arguments: Vec<Value>,
+    ) -> Result<Value, Unwind> {
+        let environment = Environment::with_enclosing(Rc::clone(&self.closure));
+        for (param, argument) in self.declaration.params.iter().zip(arguments) {
+            environment
+                .borrow_mut()
+                .define(param.lexeme.clone(), argument);
+        }
+        match interpreter.execute_body(&self.ast, &self.declaration.body, environment) {
+            Ok(()) | Err(Unwind::Return(_)) if self.is_initializer => Ok(self.this()),
+            Ok(()) => Ok(Value::Nil),
+            Err(Unwind::Return(value)) => Ok(value),
+            Err(unwind) => Err(unwind),
+        }
+    }
+}
+
+impl Display for LoxFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<fn {}>", self.name())
+    }
+}
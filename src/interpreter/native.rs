@@ -0,0 +1,52 @@
+use std::fmt;
+use std::fmt::Display;
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::value::{Callable, Value};
+use super::{Interpreter, Unwind};
+
+// A function implemented in Rust and callable from Lox.
+pub struct NativeFunction {
+    pub name: &'static str,
+    pub arity: usize,
+    pub function: fn(&[Value]) -> Value,
+}
+
+// The natives every interpreter starts with. Adding one means writing the
+// function and listing it here.
+pub fn registry() -> Vec<NativeFunction> {
+    vec![NativeFunction {
+        name: "clock",
+        arity: 0,
+        function: clock,
+    }]
+}
+
+// Seconds since the Unix epoch, for timing scripts.
+fn clock(_: &[Value]) -> Value {
+    let elapsed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    Value::number(elapsed.as_secs_f64())
+}
+
+impl Callable for NativeFunction {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn arity(&self) -> usize {
+        self.arity
+    }
+
+    fn call(self: Rc<Self>, _: &mut Interpreter, arguments: Vec<Value>) -> Result<Value, Unwind> {
+        Ok((self.function)(&arguments))
+    }
+}
+
+impl Display for NativeFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<native fn>")
+    }
+}
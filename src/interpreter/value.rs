@@ -0,0 +1,114 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::Display;
+use std::rc::Rc;
+
+use super::class::LoxClass;
+use super::{Interpreter, Unwind};
+use crate::token::number::Number;
+
+// Anything that can be called with `()`: functions, natives and classes.
+pub trait Callable: Display {
+    fn name(&self) -> &str;
+    fn arity(&self) -> usize;
+    fn call(
+        self: Rc<Self>,
+        interpreter: &mut Interpreter,
+        arguments: Vec<Value>,
+    ) -> Result<Value, Unwind>;
+}
+
+pub struct Instance {
+    pub class: Rc<LoxClass>,
+    pub fields: RefCell<HashMap<String, Value>>,
+}
+
+#[derive(Clone)]
+pub enum Value {
+    Nil,
+    Bool(bool),
+    Number(Number),
+    String(String),
+    Callable(Rc<dyn Callable>),
+    Class(Rc<LoxClass>),
+    Instance(Rc<Instance>),
+}
+
+impl Value {
+    pub fn number(value: f64) -> Value {
+        Value::Number(Number::from(value))
+    }
+
+    // Only `nil` and `false` are falsey.
+    pub fn is_truthy(&self) -> bool {
+        !matches!(self, Value::Nil | Value::Bool(false))
+    }
+}
+
+// Values of different types are never equal; functions and instances are
+// equal only to themselves.
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Nil, Value::Nil) => true,
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::Number(a), Value::Number(b)) => a == b,
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Callable(a), Value::Callable(b)) => Rc::ptr_eq(a, b),
+            (Value::Class(a), Value::Class(b)) => Rc::ptr_eq(a, b),
+            (Value::Instance(a), Value::Instance(b)) => Rc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+// How `print` shows a value. Unlike tokens, whole numbers have no `.0`.
+impl Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Nil => write!(f, "nil"),
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Number(num) => write!(f, "{}", num.value()),
+            Value::String(value) => write!(f, "{}", value),
+            Value::Callable(callable) => write!(f, "{}", callable),
+            Value::Class(class) => write!(f, "{}", class),
+            Value::Instance(instance) => write!(f, "{} instance", instance.class.name),
+        }
+    }
+}
+
+impl fmt::Debug for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::String(value) => write!(f, "{:?}", value),
+            other => write!(f, "{}", other),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!("10", Value::number(10.0).to_string());
+        assert_eq!("10.4", Value::number(10.40).to_string());
+        assert_eq!("-0.5", Value::number(-0.5).to_string());
+        assert_eq!("nil", Value::Nil.to_string());
+        assert_eq!("hi", Value::String(String::from("hi")).to_string());
+    }
+
+    #[test]
+    fn test_truthiness_and_equality() {
+        assert!(!Value::Nil.is_truthy());
+        assert!(!Value::Bool(false).is_truthy());
+        assert!(Value::number(0.0).is_truthy());
+        assert!(Value::String(String::new()).is_truthy());
+        assert_eq!(Value::Nil, Value::Nil);
+        assert_ne!(Value::Nil, Value::Bool(false));
+        assert_ne!(Value::number(1.0), Value::String(String::from("1")));
+        assert_ne!(Value::number(f64::NAN), Value::number(f64::NAN));
+    }
+}
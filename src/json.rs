@@ -0,0 +1,405 @@
+use std::fmt;
+use std::fmt::{Display, Write};
+use std::str::FromStr;
+
+pub mod sexpr;
+
+// A minimal JSON document model, enough for the machine-readable output
+// formats without pulling in a serialization framework.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn object<K: Into<String>>(fields: Vec<(K, Json)>) -> Json {
+        Json::Object(fields.into_iter().map(|(k, v)| (k.into(), v)).collect())
+    }
+
+    pub fn string<S: Into<String>>(value: S) -> Json {
+        Json::String(value.into())
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    // Multi-line rendering with two-space indentation.
+    pub fn pretty(&self) -> String {
+        let mut out = String::new();
+        self.write_pretty(&mut out, 0);
+        out
+    }
+
+    fn write_pretty(&self, out: &mut String, indent: usize) {
+        let pad = "  ".repeat(indent + 1);
+        match self {
+            Json::Array(items) if !items.is_empty() => {
+                out.push_str("[\n");
+                for (i, item) in items.iter().enumerate() {
+                    out.push_str(&pad);
+                    item.write_pretty(out, indent + 1);
+                    out.push_str(if i + 1 < items.len() { ",\n" } else { "\n" });
+                }
+                out.push_str(&"  ".repeat(indent));
+                out.push(']');
+            }
+            Json::Object(fields) if !fields.is_empty() => {
+                out.push_str("{\n");
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    out.push_str(&pad);
+                    write_string(out, key);
+                    out.push_str(": ");
+                    value.write_pretty(out, indent + 1);
+                    out.push_str(if i + 1 < fields.len() { ",\n" } else { "\n" });
+                }
+                out.push_str(&"  ".repeat(indent));
+                out.push('}');
+            }
+            other => out.push_str(&other.to_string()),
+        }
+    }
+}
+
+fn write_string(out: &mut String, value: &str) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+// Compact single-line rendering.
+impl Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            Json::Number(n) if n.is_finite() => write!(f, "{}", n),
+            // JSON has no representation for NaN or the infinities
+            Json::Number(_) => write!(f, "null"),
+            Json::String(s) => {
+                let mut out = String::new();
+                write_string(&mut out, s);
+                write!(f, "{}", out)
+            }
+            Json::Array(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(fields) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    let mut out = String::new();
+                    write_string(&mut out, key);
+                    write!(f, "{}:{}", out, value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+// Reads a document back, so machine-readable output can be used as test
+// fixtures. Errors carry the character offset where reading stopped.
+impl FromStr for Json {
+    type Err = String;
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut reader = Reader {
+            chars: text.chars().collect(),
+            current: 0,
+            notation: "JSON",
+        };
+        let json = reader.value()?;
+        reader.skip_whitespace();
+        if reader.current < reader.chars.len() {
+            return Err(reader.error("trailing characters"));
+        }
+        Ok(json)
+    }
+}
+
+struct Reader {
+    chars: Vec<char>,
+    current: usize,
+    // what is being read, for error messages
+    notation: &'static str,
+}
+
+impl Reader {
+    fn error(&self, msg: &str) -> String {
+        format!(
+            "invalid {} at offset {}: {}",
+            self.notation, self.current, msg
+        )
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.current).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r')) {
+            self.current += 1;
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.peek() == Some(c) {
+            self.current += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected `{}`", c)))
+        }
+    }
+
+    fn keyword(&mut self, word: &str, value: Json) -> Result<Json, String> {
+        for expected in word.chars() {
+            if self.peek() != Some(expected) {
+                return Err(self.error(&format!("expected `{}`", word)));
+            }
+            self.current += 1;
+        }
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('n') => self.keyword("null", Json::Null),
+            Some('t') => self.keyword("true", Json::Bool(true)),
+            Some('f') => self.keyword("false", Json::Bool(false)),
+            Some('"') => Ok(Json::String(self.string()?)),
+            Some('[') => self.array(),
+            Some('{') => self.object(),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number(),
+            Some(_) => Err(self.error("expected a value")),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    // Delimited lists: `[a, b]` and `{"k": v}` differ only in the brackets
+    // and in how an element is read.
+    fn list<T>(
+        &mut self,
+        open: char,
+        close: char,
+        mut element: impl FnMut(&mut Self) -> Result<T, String>,
+    ) -> Result<Vec<T>, String> {
+        self.expect(open)?;
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(close) {
+            self.current += 1;
+            return Ok(items);
+        }
+        loop {
+            items.push(element(self)?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.current += 1,
+                Some(c) if c == close => {
+                    self.current += 1;
+                    return Ok(items);
+                }
+                _ => return Err(self.error(&format!("expected `,` or `{}`", close))),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Json, String> {
+        self.list('[', ']', Reader::value).map(Json::Array)
+    }
+
+    fn object(&mut self) -> Result<Json, String> {
+        self.list('{', '}', |reader| {
+            reader.skip_whitespace();
+            let key = reader.string()?;
+            reader.expect(':')?;
+            Ok((key, reader.value()?))
+        })
+        .map(Json::Object)
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let start = self.current;
+        while matches!(self.peek(), Some(c) if c.is_ascii_digit() || "+-.eE".contains(c)) {
+            self.current += 1;
+        }
+        let text: String = self.chars[start..self.current].iter().collect();
+        text.parse::<f64>().map(Json::Number).map_err(|_| {
+            self.current = start;
+            self.error("invalid number")
+        })
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut value = String::new();
+        loop {
+            let c = self
+                .peek()
+                .ok_or_else(|| self.error("unterminated string"))?;
+            self.current += 1;
+            match c {
+                '"' => return Ok(value),
+                '\\' => {
+                    let escaped = self
+                        .peek()
+                        .ok_or_else(|| self.error("unterminated string"))?;
+                    self.current += 1;
+                    value.push(match escaped {
+                        '"' => '"',
+                        '\\' => '\\',
+                        '/' => '/',
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        'b' => '\u{8}',
+                        'f' => '\u{c}',
+                        'u' => self.unicode_escape()?,
+                        _ => return Err(self.error("invalid escape")),
+                    });
+                }
+                c => value.push(c),
+            }
+        }
+    }
+
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        let end = self.current + 4;
+        let hex: String = self
+            .chars
+            .get(self.current..end)
+            .unwrap_or(&[])
+            .iter()
+            .collect();
+        let code = u32::from_str_radix(&hex, 16).map_err(|_| self.error("invalid \\u escape"))?;
+        self.current = end;
+        // surrogate pairs are not needed for anything we write, so they
+        // come back as the replacement character
+        Ok(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_compact() {
+        let json = Json::object(vec![
+            ("kind", Json::string("STRING")),
+            ("literal", Json::string("a \"quoted\"\nline")),
+            ("line", Json::Number(1.0)),
+            (
+                "span",
+                Json::Array(vec![Json::Number(0.0), Json::Number(2.5)]),
+            ),
+            ("ok", Json::Bool(true)),
+            ("none", Json::Null),
+        ]);
+        assert_eq!(
+            r#"{"kind":"STRING","literal":"a \"quoted\"\nline","line":1,"span":[0,2.5],"ok":true,"none":null}"#,
+            json.to_string()
+        );
+    }
+
+    #[test]
+    fn test_pretty() {
+        let json = Json::Array(vec![Json::object(vec![("a", Json::Array(vec![]))])]);
+        assert_eq!("[\n  {\n    \"a\": []\n  }\n]", json.pretty());
+    }
+
+    #[test]
+    fn test_parse() {
+        let text = r#"{"kind":"STRING","literal":"a \"quoted\"\nline \u00e9","span":[0,-2.5e1],"ok":true,"none":null}"#;
+        let json: Json = text.parse().unwrap();
+        assert_eq!(
+            Some("a \"quoted\"\nline é"),
+            json.get("literal").and_then(Json::as_str)
+        );
+        assert_eq!(
+            Some(&[Json::Number(0.0), Json::Number(-25.0)][..]),
+            json.get("span").and_then(Json::as_array)
+        );
+        assert_eq!(Some(true), json.get("ok").and_then(Json::as_bool));
+        assert_eq!(Some(&Json::Null), json.get("none"));
+
+        let pretty = Json::Array(vec![json.clone(), Json::object::<&str>(vec![])]).pretty();
+        assert_eq!(
+            Ok(Json::Array(vec![json, Json::Object(vec![])])),
+            pretty.parse()
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            Err(String::from(
+                "invalid JSON at offset 5: expected `,` or `]`"
+            )),
+            "[1, 2".parse::<Json>()
+        );
+        assert!("{\"a\" 1}".parse::<Json>().is_err());
+        assert!("[1] x".parse::<Json>().is_err());
+        assert!("\"open".parse::<Json>().is_err());
+        assert!("tru".parse::<Json>().is_err());
+    }
+}
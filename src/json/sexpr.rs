@@ -0,0 +1,323 @@
+use super::{write_string, Json, Reader};
+
+// The same documents as JSON, written as S-expressions: an object is
+// `(Kind :key value ...)`, led by its `kind` when that is a plain word, an
+// array is `[a b c]`, null is `nil`, and strings, numbers and booleans are
+// written as in JSON. Keys must be plain words.
+
+// Lines are broken only where a document does not fit in this many columns.
+const WIDTH: usize = 80;
+
+pub fn pretty(json: &Json) -> String {
+    let mut out = String::new();
+    write_pretty(json, &mut out);
+    out
+}
+
+fn compact(json: &Json) -> String {
+    let mut out = String::new();
+    write_compact(json, &mut out);
+    out
+}
+
+fn is_word(word: &str) -> bool {
+    word.starts_with(|c: char| c.is_ascii_alphabetic())
+        && word.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !matches!(word, "nil" | "true" | "false")
+}
+
+// Splits off the `kind` that leads an object, when it can be written as one.
+fn split_kind(fields: &[(String, Json)]) -> (Option<&str>, &[(String, Json)]) {
+    match fields {
+        [(key, Json::String(kind)), rest @ ..] if key == "kind" && is_word(kind) => {
+            (Some(kind), rest)
+        }
+        _ => (None, fields),
+    }
+}
+
+fn write_compact(json: &Json, out: &mut String) {
+    match json {
+        Json::Null => out.push_str("nil"),
+        Json::Number(n) if !n.is_finite() => out.push_str("nil"),
+        Json::String(s) => write_string(out, s),
+        Json::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(' ');
+                }
+                write_compact(item, out);
+            }
+            out.push(']');
+        }
+        Json::Object(fields) => {
+            let (kind, fields) = split_kind(fields);
+            out.push('(');
+            out.push_str(kind.unwrap_or_default());
+            for (i, (key, value)) in fields.iter().enumerate() {
+                if i > 0 || kind.is_some() {
+                    out.push(' ');
+                }
+                out.push(':');
+                out.push_str(key);
+                out.push(' ');
+                write_compact(value, out);
+            }
+            out.push(')');
+        }
+        other => out.push_str(&other.to_string()),
+    }
+}
+
+// Neither an object nor an array holding one.
+fn is_atom(json: &Json) -> bool {
+    match json {
+        Json::Object(_) => false,
+        Json::Array(items) => items.iter().all(|item| !matches!(item, Json::Object(_))),
+        _ => true,
+    }
+}
+
+fn write_pretty(json: &Json, out: &mut String) {
+    let line_start = out.rfind('\n').map_or(0, |i| i + 1);
+    let flat = compact(json);
+    // atoms are never broken, however long the line
+    if is_atom(json) || out[line_start..].chars().count() + flat.chars().count() <= WIDTH {
+        out.push_str(&flat);
+        return;
+    }
+    let indent = out[line_start..].chars().take_while(|c| *c == ' ').count();
+    let pad = " ".repeat(indent + 2);
+    match json {
+        Json::Array(items) => {
+            out.push('[');
+            for item in items {
+                out.push('\n');
+                out.push_str(&pad);
+                write_pretty(item, out);
+            }
+            out.push(']');
+        }
+        Json::Object(fields) => {
+            let (kind, fields) = split_kind(fields);
+            out.push('(');
+            out.push_str(kind.unwrap_or_default());
+            // the leading atoms, such as an id and a span, stay on the first
+            // line and every other field gets its own
+            let head = fields
+                .iter()
+                .take_while(|(_, value)| is_atom(value))
+                .count();
+            for (i, (key, value)) in fields.iter().enumerate() {
+                if i < head {
+                    if i > 0 || kind.is_some() {
+                        out.push(' ');
+                    }
+                } else {
+                    out.push('\n');
+                    out.push_str(&pad);
+                }
+                out.push(':');
+                out.push_str(key);
+                out.push(' ');
+                write_pretty(value, out);
+            }
+            out.push(')');
+        }
+        _ => out.push_str(&flat),
+    }
+}
+
+// Reads every document in `text`, in order.
+pub fn parse_all(text: &str) -> Result<Vec<Json>, String> {
+    let mut reader = Reader {
+        chars: text.chars().collect(),
+        current: 0,
+        notation: "S-expression",
+    };
+    let mut documents = Vec::new();
+    loop {
+        reader.skip_whitespace();
+        if reader.peek().is_none() {
+            return Ok(documents);
+        }
+        documents.push(reader.sexpr()?);
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+impl Reader {
+    fn sexpr(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('"') => Ok(Json::String(self.string()?)),
+            Some('[') => {
+                self.current += 1;
+                self.elements(']', Reader::sexpr).map(Json::Array)
+            }
+            Some('(') => {
+                self.current += 1;
+                self.sexpr_object()
+            }
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number(),
+            Some(c) if c.is_ascii_alphabetic() => {
+                let start = self.current;
+                match self.word().as_str() {
+                    "nil" => Ok(Json::Null),
+                    "true" => Ok(Json::Bool(true)),
+                    "false" => Ok(Json::Bool(false)),
+                    _ => {
+                        self.current = start;
+                        Err(self.error("expected a value"))
+                    }
+                }
+            }
+            Some(_) => Err(self.error("expected a value")),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn word(&mut self) -> String {
+        let start = self.current;
+        while matches!(self.peek(), Some(c) if is_word_char(c)) {
+            self.current += 1;
+        }
+        self.chars[start..self.current].iter().collect()
+    }
+
+    // Whitespace-separated elements up to `close`, whose opener has been read.
+    fn elements<T>(
+        &mut self,
+        close: char,
+        mut element: impl FnMut(&mut Self) -> Result<T, String>,
+    ) -> Result<Vec<T>, String> {
+        let mut items = Vec::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some(c) if c == close => {
+                    self.current += 1;
+                    return Ok(items);
+                }
+                Some(_) => items.push(element(self)?),
+                None => return Err(self.error(&format!("expected `{}`", close))),
+            }
+        }
+    }
+
+    fn sexpr_object(&mut self) -> Result<Json, String> {
+        let mut fields = Vec::new();
+        self.skip_whitespace();
+        if matches!(self.peek(), Some(c) if c.is_ascii_alphabetic()) {
+            fields.push((String::from("kind"), Json::String(self.word())));
+        }
+        fields.extend(self.elements(')', |reader| {
+            reader.expect(':')?;
+            let key = reader.word();
+            if key.is_empty() {
+                return Err(reader.error("expected a key"));
+            }
+            Ok((key, reader.sexpr()?))
+        })?);
+        Ok(Json::Object(fields))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_compact() {
+        let json = Json::object(vec![
+            ("kind", Json::string("STRING")),
+            ("literal", Json::string("a \"quoted\"\nline")),
+            (
+                "span",
+                Json::Array(vec![Json::Number(0.0), Json::Number(2.5)]),
+            ),
+            ("ok", Json::Bool(true)),
+            ("none", Json::Null),
+        ]);
+        assert_eq!(
+            r#"(STRING :literal "a \"quoted\"\nline" :span [0 2.5] :ok true :none nil)"#,
+            pretty(&json)
+        );
+        let untyped = Json::object(vec![
+            ("kind", Json::string("not a word")),
+            ("a", Json::Null),
+        ]);
+        assert_eq!(r#"(:kind "not a word" :a nil)"#, pretty(&untyped));
+    }
+
+    #[test]
+    fn test_pretty() {
+        let leaf = |name: &str| {
+            Json::object(vec![
+                ("kind", Json::string("Leaf")),
+                ("name", Json::string(name)),
+                ("padding", Json::string("x".repeat(40))),
+            ])
+        };
+        let json = Json::object(vec![
+            ("kind", Json::string("Tree")),
+            ("id", Json::Number(1.0)),
+            ("children", Json::Array(vec![leaf("a"), leaf("b")])),
+            ("last", Json::Bool(false)),
+        ]);
+        let padding = "x".repeat(40);
+        assert_eq!(
+            format!(
+                "(Tree :id 1\n  :children [\n    (Leaf :name \"a\" :padding \"{0}\")\n    (Leaf :name \"b\" :padding \"{0}\")]\n  :last false)",
+                padding
+            ),
+            pretty(&json)
+        );
+        assert_eq!(Ok(vec![json.clone()]), parse_all(&pretty(&json)));
+    }
+
+    #[test]
+    fn test_parse_all() {
+        let text = r#"(Print :id 0 :span [0 -2.5e1]) [] (:a "é")
+            (Literal :value nil :ok true)"#;
+        assert_eq!(
+            Ok(vec![
+                Json::object(vec![
+                    ("kind", Json::string("Print")),
+                    ("id", Json::Number(0.0)),
+                    (
+                        "span",
+                        Json::Array(vec![Json::Number(0.0), Json::Number(-25.0)])
+                    ),
+                ]),
+                Json::Array(vec![]),
+                Json::object(vec![("a", Json::string("é"))]),
+                Json::object(vec![
+                    ("kind", Json::string("Literal")),
+                    ("value", Json::Null),
+                    ("ok", Json::Bool(true)),
+                ]),
+            ]),
+            parse_all(text)
+        );
+        assert_eq!(Ok(vec![]), parse_all("  \n"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            Err(String::from(
+                "invalid S-expression at offset 5: expected `]`"
+            )),
+            parse_all("[1 2 ")
+        );
+        assert!(parse_all("(Print :id)").is_err());
+        assert!(parse_all("(Print id 1)").is_err());
+        assert!(parse_all("(Print :id 1").is_err());
+        assert!(parse_all("null").is_err());
+    }
+}
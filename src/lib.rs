@@ -1,24 +1,191 @@
+pub mod ast;
+pub mod cli;
 pub mod errors;
+pub mod interpreter;
+pub mod json;
+pub mod parser;
+pub mod resolver;
 pub mod scanner;
 pub mod superiterator;
 pub mod token;
 
-use std::process;
-
-use errors::ErrorHandler;
+use ast::{Ast, NodeMap};
+use errors::lint::LintConfig;
+use errors::{Diagnostic, ErrorHandler};
+use parser::Parser;
+use resolver::Resolver;
 use scanner::Scanner;
-// use token::{Token, TokenType};
+use token::{Dialect, Token};
+
+// Settings shared by every phase of the library API.
+#[derive(Debug, Default, Clone)]
+pub struct Options {
+    pub dialect: Dialect,
+    pub lints: LintConfig,
+}
+
+#[derive(Debug)]
+pub struct ScanResult {
+    pub tokens: Vec<Token>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ScanResult {
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics.iter().any(Diagnostic::is_error)
+    }
+}
+
+pub fn scan(source_code: &str) -> ScanResult {
+    scan_with(source_code, &Options::default())
+}
+
+pub fn scan_with(source_code: &str, options: &Options) -> ScanResult {
+    let mut error_handler = ErrorHandler::new();
+    error_handler.set_lints(options.lints.clone());
+    let tokens = Scanner::new(source_code, &mut error_handler)
+        .with_dialect(options.dialect)
+        .scan_source();
+    ScanResult {
+        tokens,
+        diagnostics: error_handler.into_diagnostics(),
+    }
+}
+
+#[derive(Debug)]
+pub struct ParseResult<T> {
+    // `None` when the source had errors that prevented building a tree
+    pub ast: Option<T>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl<T> ParseResult<T> {
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics.iter().any(Diagnostic::is_error)
+    }
+}
+
+// The tree's one root is the expression.
+pub fn parse_expression(source_code: &str) -> ParseResult<Ast> {
+    parse_expression_with(source_code, &Options::default())
+}
+
+pub fn parse_expression_with(source_code: &str, options: &Options) -> ParseResult<Ast> {
+    parse_tokens(source_code, options, |parser| parser.parse_expression())
+}
+
+// The tree's roots are the top-level statements.
+pub fn parse_program(source_code: &str) -> ParseResult<Ast> {
+    parse_program_with(source_code, &Options::default())
+}
+
+pub fn parse_program_with(source_code: &str, options: &Options) -> ParseResult<Ast> {
+    parse_tokens(source_code, options, |parser| parser.parse())
+}
+
+#[derive(Debug)]
+pub struct ResolveResult {
+    // scope depth of each local variable reference, keyed by expression id
+    pub locals: NodeMap<usize>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ResolveResult {
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics.iter().any(Diagnostic::is_error)
+    }
+}
+
+pub fn resolve(ast: &Ast) -> ResolveResult {
+    resolve_with(ast, &Options::default())
+}
+
+pub fn resolve_with(ast: &Ast, options: &Options) -> ResolveResult {
+    let mut error_handler = ErrorHandler::new();
+    error_handler.set_lints(options.lints.clone());
+    let locals = Resolver::new(&mut error_handler).resolve(ast);
+    ResolveResult {
+        locals,
+        diagnostics: error_handler.into_diagnostics(),
+    }
+}
 
-pub fn tokenize(source_code: &str) {
+// Scanning errors stop the pipeline before the parser runs, so diagnostics
+// are never reported about tokens that were not really there.
+fn parse_tokens<T>(
+    source_code: &str,
+    options: &Options,
+    parse: impl FnOnce(&mut Parser) -> Option<T>,
+) -> ParseResult<T> {
+    let scanned = scan_with(source_code, options);
+    if scanned.has_errors() {
+        return ParseResult {
+            ast: None,
+            diagnostics: scanned.diagnostics,
+        };
+    }
     let mut error_handler = ErrorHandler::new();
-    let mut scanner = Scanner::new(source_code, &mut error_handler);
-    let tokens = scanner.scan_source();
+    error_handler.set_lints(options.lints.clone());
+    error_handler.extend(scanned.diagnostics);
+    let ast = parse(&mut Parser::new(scanned.tokens, &mut error_handler));
+    ParseResult {
+        ast,
+        diagnostics: error_handler.into_diagnostics(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use token::TokenType;
 
-    error_handler.display_errors(source_code);
-    for token in tokens {
-        println!("{}", token.token_type);
+    #[test]
+    fn test_scan() {
+        let result = scan("print 1 # 2;");
+        assert!(result.has_errors());
+        assert_eq!(5, result.tokens.len());
+        assert_eq!(TokenType::Print, result.tokens[0].token_type);
+        assert_eq!(
+            vec!["[line 1] Error: Unexpected character: #"],
+            result
+                .diagnostics
+                .iter()
+                .map(|d| d.to_string())
+                .collect::<Vec<String>>()
+        );
     }
-    if error_handler.has_error() {
-        process::exit(65);
+
+    #[test]
+    fn test_scan_with_options() {
+        let mut options = Options {
+            dialect: Dialect::Extended,
+            ..Options::default()
+        };
+        options.lints.apply_flag("-A", "warnings").unwrap();
+        let result = scan_with("p\u{0430}y % 2", &options);
+        assert!(result.diagnostics.is_empty());
+        assert_eq!(TokenType::Percent, result.tokens[1].token_type);
+    }
+
+    #[test]
+    fn test_parse_expression() {
+        let result = parse_expression("-(1 + 2) * 3");
+        assert!(!result.has_errors());
+        let ast = result.ast.unwrap();
+        assert_eq!(
+            "(* (- (group (+ 1.0 2.0))) 3.0)",
+            ast.display(ast.root()).to_string()
+        );
+
+        let result = parse_expression("1 + @");
+        assert!(result.ast.is_none());
+        assert_eq!(1, result.diagnostics.len());
+    }
+
+    #[test]
+    fn test_parse_program() {
+        let result = parse_program("var a = 1;\nprint a;");
+        assert!(!result.has_errors());
+        assert_eq!(2, result.ast.unwrap().roots().len());
     }
 }
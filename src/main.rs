@@ -1,36 +1,19 @@
 use std::env;
-use std::fs;
+use std::process;
 
-use codecrafters_interpreter::tokenize;
+use codecrafters_interpreter::cli;
+use codecrafters_interpreter::cli::error::EX_OK;
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() < 3 {
-        eprintln!("Usage: {} tokenize <filename>", args[0]);
-        return;
-    }
-
-    let command = &args[1];
-    let filename = &args[2];
-
-    match command.as_str() {
-        "tokenize" => {
-            // You can use print statements as follows for debugging, they'll be visible when running tests.
-
-            let file_contents = fs::read_to_string(filename).unwrap_or_else(|_| {
-                let msg = format!("Failed to read file {}", filename);
-                eprintln!("{}", msg);
-                msg
-            });
-
-            if !file_contents.is_empty() {
-                tokenize(&file_contents);
-            } else {
-                println!("EOF  null"); // Placeholder, remove this line when implementing the scanner
+    let args: Vec<String> = env::args().skip(1).collect();
+    let code = match cli::run(&args) {
+        Ok(()) => EX_OK,
+        Err(e) => {
+            if !e.is_reported() {
+                eprintln!("{}", e);
             }
+            e.exit_code()
         }
-        _ => {
-            eprintln!("Unknown command: {}", command);
-        }
-    }
+    };
+    process::exit(code);
 }
@@ -0,0 +1,1112 @@
+use maplit::hashmap;
+use once_cell::sync::Lazy;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use crate::ast::{Ast, ExprKind, FunctionDecl, LiteralValue, NodeId, StmtKind};
+use crate::errors::suggest::{did_you_mean, suggest};
+use crate::errors::{ErrorCode, ErrorHandler};
+use crate::token::{Span, Token, TokenType};
+
+// Marks that an error has been reported and parsing of the current
+// construct has been abandoned.
+#[derive(Debug)]
+pub struct ParseError;
+
+type ParseResult<T> = Result<T, ParseError>;
+
+const MAX_ARGUMENTS: usize = 255;
+
+// Binding powers, loosest first. Calls and property accesses bind tighter
+// than any operator and are parsed by `call`.
+pub(crate) mod precedence {
+    pub const ASSIGNMENT: u8 = 1;
+    pub const CONDITIONAL: u8 = 2;
+    pub const OR: u8 = 3;
+    pub const AND: u8 = 4;
+    pub const EQUALITY: u8 = 5;
+    pub const COMPARISON: u8 = 6;
+    pub const TERM: u8 = 7;
+    pub const FACTOR: u8 = 8;
+    pub const UNARY: u8 = 9;
+    // above unary, so that `-2 ** 2` is `-(2 ** 2)`
+    pub const EXPONENT: u8 = 10;
+    pub const CALL: u8 = 11;
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) enum Associativity {
+    Left,
+    Right,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum InfixKind {
+    Assign,
+    Conditional,
+    Logical,
+    Binary,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct InfixOperator {
+    precedence: u8,
+    associativity: Associativity,
+    kind: InfixKind,
+}
+
+const fn infix(precedence: u8, associativity: Associativity, kind: InfixKind) -> InfixOperator {
+    InfixOperator {
+        precedence,
+        associativity,
+        kind,
+    }
+}
+
+// Operators of the extended dialect are listed too; the scanner only
+// produces their tokens when that dialect is enabled.
+static INFIX_OPERATORS: Lazy<HashMap<TokenType, InfixOperator>> = Lazy::new(|| {
+    use precedence::*;
+    use Associativity::*;
+    use InfixKind::*;
+    hashmap! {
+        TokenType::Equal => infix(ASSIGNMENT, Right, Assign),
+        TokenType::Question => infix(CONDITIONAL, Right, Conditional),
+        TokenType::Or => infix(OR, Left, Logical),
+        TokenType::And => infix(AND, Left, Logical),
+        TokenType::EqualEqual => infix(EQUALITY, Left, Binary),
+        TokenType::BangEqual => infix(EQUALITY, Left, Binary),
+        TokenType::Greater => infix(COMPARISON, Left, Binary),
+        TokenType::GreaterEqual => infix(COMPARISON, Left, Binary),
+        TokenType::Less => infix(COMPARISON, Left, Binary),
+        TokenType::LessEqual => infix(COMPARISON, Left, Binary),
+        TokenType::Plus => infix(TERM, Left, Binary),
+        TokenType::Minus => infix(TERM, Left, Binary),
+        TokenType::Star => infix(FACTOR, Left, Binary),
+        TokenType::Slash => infix(FACTOR, Left, Binary),
+        TokenType::Percent => infix(FACTOR, Left, Binary),
+        TokenType::StarStar => infix(EXPONENT, Right, Binary),
+    }
+});
+
+// How tightly an infix operator binds, for printing trees back out.
+pub(crate) fn infix_binding(token_type: &TokenType) -> Option<(u8, Associativity)> {
+    INFIX_OPERATORS
+        .get(token_type)
+        .map(|operator| (operator.precedence, operator.associativity))
+}
+
+// Whether `token_type` can begin an expression.
+fn starts_expression(token_type: &TokenType) -> bool {
+    PREFIX_OPERATORS.contains_key(token_type)
+        || matches!(
+            token_type,
+            TokenType::Identifier(_)
+                | TokenType::Number(..)
+                | TokenType::String(_)
+                | TokenType::True
+                | TokenType::False
+                | TokenType::Nil
+                | TokenType::This
+                | TokenType::Super
+                | TokenType::LeftParen
+        )
+}
+
+// Whether a statement beginning with `keyword` could continue with `next`.
+fn keyword_fits(keyword: &str, next: &TokenType) -> bool {
+    match keyword {
+        "class" | "fun" | "var" => matches!(next, TokenType::Identifier(_)),
+        "for" | "if" | "while" => *next == TokenType::LeftParen,
+        "print" => starts_expression(next),
+        "return" => *next == TokenType::Semicolon || starts_expression(next),
+        _ => false,
+    }
+}
+
+// Prefix operators and the binding power of their operand.
+static PREFIX_OPERATORS: Lazy<HashMap<TokenType, u8>> = Lazy::new(|| {
+    hashmap! {
+        TokenType::Bang => precedence::UNARY,
+        TokenType::Minus => precedence::UNARY,
+    }
+});
+
+pub struct Parser<'a> {
+    tokens: Vec<Token>,
+    current: usize,
+    had_error: bool,
+    // number of enclosing blocks, so recovery can stop at a closing `}`
+    block_depth: usize,
+    // every kind of token that was checked for at the current position,
+    // which is what would have been accepted there
+    expected: Vec<TokenType>,
+    expected_expression: bool,
+    // index of the token the innermost statement began with
+    statement_start: usize,
+    // every name declared so far, which are never taken for misspelled
+    // keywords
+    declared: HashSet<String>,
+    // the nodes built so far
+    ast: Ast,
+    error_handler: &'a mut ErrorHandler,
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(tokens: Vec<Token>, error_handler: &'a mut ErrorHandler) -> Self {
+        Parser {
+            tokens,
+            current: 0,
+            had_error: false,
+            block_depth: 0,
+            expected: Vec::new(),
+            expected_expression: false,
+            statement_start: 0,
+            declared: HashSet::new(),
+            ast: Ast::new(),
+            error_handler,
+        }
+    }
+
+    pub fn parse_expression(&mut self) -> Option<Ast> {
+        let expr = self.expression().ok()?;
+        if !self.is_at_end() {
+            self.expected.push(TokenType::EOF);
+            let token = self.peek().clone();
+            self.error(
+                ErrorCode::UnexpectedToken,
+                &token,
+                "Expect end of expression.",
+            );
+            return None;
+        }
+        Some(self.finish(vec![expr]))
+    }
+
+    // Parses the whole program, reporting every independent syntax error.
+    // Returns `None` if there was any.
+    pub fn parse(&mut self) -> Option<Ast> {
+        let mut statements = Vec::new();
+        while !self.is_at_end() {
+            if let Some(stmt) = self.declaration_or_synchronize() {
+                statements.push(stmt);
+            }
+        }
+        if self.had_error {
+            None
+        } else {
+            Some(self.finish(statements))
+        }
+    }
+
+    fn finish(&mut self, roots: Vec<NodeId>) -> Ast {
+        let mut ast = std::mem::take(&mut self.ast);
+        ast.set_roots(roots);
+        ast
+    }
+
+    // On a syntax error, skips ahead to the next likely statement boundary
+    // so that parsing can resume there.
+    fn declaration_or_synchronize(&mut self) -> Option<NodeId> {
+        match self.declaration() {
+            Ok(stmt) => Some(stmt),
+            Err(ParseError) => {
+                self.synchronize();
+                None
+            }
+        }
+    }
+
+    fn synchronize(&mut self) {
+        // inside a block, a `}` is left for the block to close itself
+        let at_block_end = |parser: &Self| {
+            parser.block_depth > 0 && parser.peek().token_type == TokenType::RightBrace
+        };
+        if at_block_end(self) {
+            return;
+        }
+        self.advance();
+        while !self.is_at_end() && !at_block_end(self) {
+            if self.previous().token_type == TokenType::Semicolon {
+                return;
+            }
+            match self.peek().token_type {
+                TokenType::Class
+                | TokenType::Fun
+                | TokenType::Var
+                | TokenType::For
+                | TokenType::If
+                | TokenType::While
+                | TokenType::Print
+                | TokenType::Return => return,
+                _ => {
+                    self.advance();
+                }
+            }
+        }
+    }
+
+    fn declaration(&mut self) -> ParseResult<NodeId> {
+        self.statement_start = self.current;
+        if self.match_any(&[TokenType::Class]) {
+            self.class_declaration()
+        } else if self.match_any(&[TokenType::Fun]) {
+            let start = self.previous().span;
+            let function = self.function("function")?;
+            Ok(self.stmt(start, StmtKind::Function(Rc::new(function))))
+        } else if self.match_any(&[TokenType::Var]) {
+            self.var_declaration()
+        } else {
+            self.statement()
+        }
+    }
+
+    fn class_declaration(&mut self) -> ParseResult<NodeId> {
+        let start = self.previous().span;
+        let name = self.consume_identifier("Expect class name.")?;
+        self.declared.insert(name.lexeme.clone());
+        let superclass = if self.match_any(&[TokenType::Less]) {
+            let name = self.consume_identifier("Expect superclass name.")?;
+            Some(self.expr(name.span, ExprKind::Variable { name }))
+        } else {
+            None
+        };
+        self.consume(TokenType::LeftBrace, "Expect '{' before class body.")?;
+        let mut methods = Vec::new();
+        while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
+            methods.push(Rc::new(self.function("method")?));
+        }
+        self.consume(TokenType::RightBrace, "Expect '}' after class body.")?;
+        Ok(self.stmt(
+            start,
+            StmtKind::Class {
+                name,
+                superclass,
+                methods,
+            },
+        ))
+    }
+
+    // `kind` is "function" or "method", for error messages.
+    fn function(&mut self, kind: &str) -> ParseResult<FunctionDecl> {
+        let name = self.consume_identifier(&format!("Expect {} name.", kind))?;
+        self.declared.insert(name.lexeme.clone());
+        self.consume(
+            TokenType::LeftParen,
+            &format!("Expect '(' after {} name.", kind),
+        )?;
+        let mut params = Vec::new();
+        if !self.check(&TokenType::RightParen) {
+            loop {
+                if params.len() >= MAX_ARGUMENTS {
+                    let token = self.peek().clone();
+                    self.error(
+                        ErrorCode::TooManyArguments,
+                        &token,
+                        "Can't have more than 255 parameters.",
+                    );
+                }
+                let param = self.consume_identifier("Expect parameter name.")?;
+                self.declared.insert(param.lexeme.clone());
+                params.push(param);
+                if !self.match_any(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+        self.consume(TokenType::RightParen, "Expect ')' after parameters.")?;
+        self.consume(
+            TokenType::LeftBrace,
+            &format!("Expect '{{' before {} body.", kind),
+        )?;
+        let body = self.block()?;
+        Ok(FunctionDecl { name, params, body })
+    }
+
+    fn var_declaration(&mut self) -> ParseResult<NodeId> {
+        let start = self.previous().span;
+        let name = self.consume_identifier("Expect variable name.")?;
+        self.declared.insert(name.lexeme.clone());
+        let initializer = if self.match_any(&[TokenType::Equal]) {
+            Some(self.expression()?)
+        } else {
+            None
+        };
+        self.consume(
+            TokenType::Semicolon,
+            "Expect ';' after variable declaration.",
+        )?;
+        Ok(self.stmt(start, StmtKind::Var { name, initializer }))
+    }
+
+    fn statement(&mut self) -> ParseResult<NodeId> {
+        self.statement_start = self.current;
+        if self.match_any(&[TokenType::For]) {
+            self.for_statement()
+        } else if self.match_any(&[TokenType::If]) {
+            self.if_statement()
+        } else if self.match_any(&[TokenType::Print]) {
+            let start = self.previous().span;
+            let expr = self.expression()?;
+            self.consume(TokenType::Semicolon, "Expect ';' after value.")?;
+            Ok(self.stmt(start, StmtKind::Print { expr }))
+        } else if self.match_any(&[TokenType::Return]) {
+            self.return_statement()
+        } else if self.match_any(&[TokenType::While]) {
+            self.while_statement()
+        } else if self.match_any(&[TokenType::LeftBrace]) {
+            let start = self.previous().span;
+            let statements = self.block()?;
+            Ok(self.stmt(start, StmtKind::Block { statements }))
+        } else {
+            self.expression_statement()
+        }
+    }
+
+    fn expression_statement(&mut self) -> ParseResult<NodeId> {
+        let start = self.peek().span;
+        let expr = self.expression()?;
+        self.consume(TokenType::Semicolon, "Expect ';' after expression.")?;
+        Ok(self.stmt(start, StmtKind::Expression { expr }))
+    }
+
+    // There is no for node: `for (init; cond; incr) body` becomes
+    // `{ init; while (cond) { body; incr; } }`.
+    fn for_statement(&mut self) -> ParseResult<NodeId> {
+        let for_token = self.previous().clone();
+        self.consume(TokenType::LeftParen, "Expect '(' after 'for'.")?;
+        let initializer = if self.match_any(&[TokenType::Semicolon]) {
+            None
+        } else if self.match_any(&[TokenType::Var]) {
+            Some(self.var_declaration()?)
+        } else {
+            Some(self.expression_statement()?)
+        };
+        let condition = if self.check(&TokenType::Semicolon) {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+        self.consume(TokenType::Semicolon, "Expect ';' after loop condition.")?;
+        let increment = if self.check(&TokenType::RightParen) {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+        self.consume(TokenType::RightParen, "Expect ')' after for clauses.")?;
+
+        // the nodes made up here span the whole loop
+        let mut body = self.statement()?;
+        if let Some(increment) = increment {
+            let increment = self.stmt(
+                self.ast.span(increment),
+                StmtKind::Expression { expr: increment },
+            );
+            body = self.stmt(
+                for_token.span,
+                StmtKind::Block {
+                    statements: vec![body, increment],
+                },
+            );
+        }
+        let condition = match condition {
+            Some(condition) => condition,
+            None => self.expr(
+                for_token.span,
+                ExprKind::Literal {
+                    value: LiteralValue::Bool(true),
+                },
+            ),
+        };
+        body = self.stmt(for_token.span, StmtKind::While { condition, body });
+        if let Some(initializer) = initializer {
+            body = self.stmt(
+                for_token.span,
+                StmtKind::Block {
+                    statements: vec![initializer, body],
+                },
+            );
+        }
+        Ok(body)
+    }
+
+    fn if_statement(&mut self) -> ParseResult<NodeId> {
+        let start = self.previous().span;
+        self.consume(TokenType::LeftParen, "Expect '(' after 'if'.")?;
+        let condition = self.expression()?;
+        self.consume(TokenType::RightParen, "Expect ')' after if condition.")?;
+        let then_branch = self.statement()?;
+        let else_branch = if self.match_any(&[TokenType::Else]) {
+            Some(self.statement()?)
+        } else {
+            None
+        };
+        Ok(self.stmt(
+            start,
+            StmtKind::If {
+                condition,
+                then_branch,
+                else_branch,
+            },
+        ))
+    }
+
+    fn return_statement(&mut self) -> ParseResult<NodeId> {
+        let keyword = self.previous().clone();
+        let value = if self.check(&TokenType::Semicolon) {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+        self.consume(TokenType::Semicolon, "Expect ';' after return value.")?;
+        Ok(self.stmt(keyword.span, StmtKind::Return { keyword, value }))
+    }
+
+    fn while_statement(&mut self) -> ParseResult<NodeId> {
+        let start = self.previous().span;
+        self.consume(TokenType::LeftParen, "Expect '(' after 'while'.")?;
+        let condition = self.expression()?;
+        self.consume(TokenType::RightParen, "Expect ')' after condition.")?;
+        let body = self.statement()?;
+        Ok(self.stmt(start, StmtKind::While { condition, body }))
+    }
+
+    // Parses the statements of a block whose `{` has been consumed.
+    fn block(&mut self) -> ParseResult<Vec<NodeId>> {
+        let mut statements = Vec::new();
+        self.block_depth += 1;
+        while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
+            if let Some(stmt) = self.declaration_or_synchronize() {
+                statements.push(stmt);
+            }
+        }
+        self.block_depth -= 1;
+        self.consume(TokenType::RightBrace, "Expect '}' after block.")?;
+        Ok(statements)
+    }
+
+    fn expression(&mut self) -> ParseResult<NodeId> {
+        self.parse_precedence(precedence::ASSIGNMENT)
+    }
+
+    // Parses an expression whose operators all bind at least as tightly as
+    // `min_precedence`, looking each operator up in the tables above.
+    fn parse_precedence(&mut self, min_precedence: u8) -> ParseResult<NodeId> {
+        let mut expr = self.prefix()?;
+        loop {
+            let operator = INFIX_OPERATORS
+                .get(&self.peek().token_type)
+                .filter(|operator| operator.precedence >= min_precedence);
+            let Some(&operator) = operator else {
+                self.expect_operators(min_precedence);
+                return Ok(expr);
+            };
+            let token = self.advance().clone();
+            let min_right = match operator.associativity {
+                Associativity::Left => operator.precedence + 1,
+                Associativity::Right => operator.precedence,
+            };
+            expr = self.infix(expr, token, operator.kind, min_right)?;
+        }
+    }
+
+    fn prefix(&mut self) -> ParseResult<NodeId> {
+        if let Some(&precedence) = PREFIX_OPERATORS.get(&self.peek().token_type) {
+            let operator = self.advance().clone();
+            let right = self.parse_precedence(precedence)?;
+            return Ok(self.expr(
+                operator.span.to(self.ast.span(right)),
+                ExprKind::Unary { operator, right },
+            ));
+        }
+        for token_type in PREFIX_OPERATORS.keys() {
+            self.check(token_type);
+        }
+        self.call()
+    }
+
+    fn infix(
+        &mut self,
+        left: NodeId,
+        operator: Token,
+        kind: InfixKind,
+        min_right: u8,
+    ) -> ParseResult<NodeId> {
+        match kind {
+            InfixKind::Assign => {
+                let value = self.parse_precedence(min_right)?;
+                let span = self.ast.span(left).to(self.ast.span(value));
+                // the target's node becomes the assignment, so that no node
+                // is left out of the tree
+                let target = self.ast.expr_mut(left);
+                let kind = match &target.kind {
+                    ExprKind::Variable { name } => ExprKind::Assign {
+                        name: name.clone(),
+                        value,
+                    },
+                    ExprKind::Get { object, name } => ExprKind::Set {
+                        object: *object,
+                        name: name.clone(),
+                        value,
+                    },
+                    // reported without unwinding: the parser is not confused
+                    _ => {
+                        self.error(
+                            ErrorCode::InvalidAssignmentTarget,
+                            &operator,
+                            "Invalid assignment target.",
+                        );
+                        return Ok(left);
+                    }
+                };
+                target.span = span;
+                target.kind = kind;
+                Ok(left)
+            }
+            InfixKind::Conditional => {
+                let then_branch = self.expression()?;
+                self.consume(
+                    TokenType::Colon,
+                    "Expect ':' after then branch of conditional expression.",
+                )?;
+                let else_branch = self.parse_precedence(min_right)?;
+                Ok(self.expr(
+                    self.ast.span(left).to(self.ast.span(else_branch)),
+                    ExprKind::Conditional {
+                        condition: left,
+                        then_branch,
+                        else_branch,
+                    },
+                ))
+            }
+            InfixKind::Logical | InfixKind::Binary => {
+                let right = self.parse_precedence(min_right)?;
+                let span = self.ast.span(left).to(self.ast.span(right));
+                let kind = if kind == InfixKind::Logical {
+                    ExprKind::Logical {
+                        left,
+                        operator,
+                        right,
+                    }
+                } else {
+                    ExprKind::Binary {
+                        left,
+                        operator,
+                        right,
+                    }
+                };
+                Ok(self.expr(span, kind))
+            }
+        }
+    }
+
+    // Any operator that could have continued the expression was expected.
+    fn expect_operators(&mut self, min_precedence: u8) {
+        for (token_type, operator) in INFIX_OPERATORS.iter() {
+            if operator.precedence >= min_precedence && !self.expected.contains(token_type) {
+                self.expected.push(token_type.clone());
+            }
+        }
+    }
+
+    fn call(&mut self) -> ParseResult<NodeId> {
+        let mut expr = self.primary()?;
+        loop {
+            if self.match_any(&[TokenType::LeftParen]) {
+                expr = self.finish_call(expr)?;
+            } else if self.match_any(&[TokenType::Dot]) {
+                let name = self.consume_identifier("Expect property name after '.'.")?;
+                expr = self.expr(
+                    self.ast.span(expr).to(name.span),
+                    ExprKind::Get { object: expr, name },
+                );
+            } else {
+                break;
+            }
+        }
+        Ok(expr)
+    }
+
+    fn finish_call(&mut self, callee: NodeId) -> ParseResult<NodeId> {
+        let mut arguments = Vec::new();
+        if !self.check(&TokenType::RightParen) {
+            loop {
+                if arguments.len() >= MAX_ARGUMENTS {
+                    let token = self.peek().clone();
+                    self.error(
+                        ErrorCode::TooManyArguments,
+                        &token,
+                        "Can't have more than 255 arguments.",
+                    );
+                }
+                arguments.push(self.expression()?);
+                if !self.match_any(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+        let paren = self.consume(TokenType::RightParen, "Expect ')' after arguments.")?;
+        Ok(self.expr(
+            self.ast.span(callee).to(paren.span),
+            ExprKind::Call {
+                callee,
+                paren,
+                arguments,
+            },
+        ))
+    }
+
+    fn primary(&mut self) -> ParseResult<NodeId> {
+        let token = self.peek().clone();
+        let value = match &token.token_type {
+            TokenType::False => LiteralValue::Bool(false),
+            TokenType::True => LiteralValue::Bool(true),
+            TokenType::Nil => LiteralValue::Nil,
+            TokenType::Number(num, _) => LiteralValue::Number(num.clone()),
+            TokenType::String(value) => LiteralValue::String(value.clone()),
+            TokenType::Identifier(_) => {
+                self.advance();
+                return Ok(self.expr(token.span, ExprKind::Variable { name: token }));
+            }
+            TokenType::This => {
+                self.advance();
+                return Ok(self.expr(token.span, ExprKind::This { keyword: token }));
+            }
+            TokenType::Super => {
+                self.advance();
+                self.consume(TokenType::Dot, "Expect '.' after 'super'.")?;
+                let method = self.consume_identifier("Expect superclass method name.")?;
+                return Ok(self.expr(
+                    token.span.to(method.span),
+                    ExprKind::Super {
+                        keyword: token,
+                        method,
+                    },
+                ));
+            }
+            TokenType::LeftParen => {
+                self.advance();
+                let expr = self.expression()?;
+                let right_paren =
+                    self.consume(TokenType::RightParen, "Expect ')' after expression.")?;
+                return Ok(self.expr(token.span.to(right_paren.span), ExprKind::Grouping { expr }));
+            }
+            _ => {
+                self.expected_expression = true;
+                return Err(self.error(
+                    ErrorCode::ExpectedExpression,
+                    &token,
+                    "Expect expression.",
+                ));
+            }
+        };
+        self.advance();
+        Ok(self.expr(token.span, ExprKind::Literal { value }))
+    }
+
+    fn expr(&mut self, span: Span, kind: ExprKind) -> NodeId {
+        self.ast.add_expr(span, kind)
+    }
+
+    // A statement from `start` up to the last token consumed.
+    fn stmt(&mut self, start: Span, kind: StmtKind) -> NodeId {
+        let span = start.to(self.previous().span);
+        self.ast.add_stmt(span, kind)
+    }
+
+    fn consume(&mut self, token_type: TokenType, message: &str) -> ParseResult<Token> {
+        if self.check(&token_type) {
+            return Ok(self.advance().clone());
+        }
+        let token = self.peek().clone();
+        Err(self.error(ErrorCode::UnexpectedToken, &token, message))
+    }
+
+    fn consume_identifier(&mut self, message: &str) -> ParseResult<Token> {
+        if let TokenType::Identifier(_) = self.peek().token_type {
+            return Ok(self.advance().clone());
+        }
+        self.expected.push(TokenType::Identifier(String::new()));
+        let token = self.peek().clone();
+        Err(self.error(ErrorCode::UnexpectedToken, &token, message))
+    }
+
+    fn error(&mut self, code: ErrorCode, token: &Token, message: &str) -> ParseError {
+        self.had_error = true;
+        self.error_handler
+            .report_token(code, String::from(message), token);
+        if token.span == self.peek().span {
+            let expected = self.describe_expected();
+            if !expected.is_empty() {
+                self.error_handler.add_label(format!(
+                    "expected {}, found {}",
+                    expected,
+                    token.token_type.describe()
+                ));
+            }
+        }
+        if let Some(keyword) = self.misspelled_keyword() {
+            self.error_handler.add_help(did_you_mean(keyword));
+        }
+        ParseError
+    }
+
+    // `retrun x;` scans as two identifiers in a row, so an error right after
+    // an undeclared identifier that began the statement may be a misspelled
+    // keyword, if what follows would fit after that keyword.
+    fn misspelled_keyword(&self) -> Option<&'static str> {
+        if self.current == 0 || self.current - 1 != self.statement_start {
+            return None;
+        }
+        let TokenType::Identifier(name) = &self.previous().token_type else {
+            return None;
+        };
+        if name.chars().count() < 3 || self.declared.contains(name) {
+            return None;
+        }
+        let next = &self.peek().token_type;
+        let candidates = crate::token::keywords()
+            .into_iter()
+            .filter(|keyword| keyword_fits(keyword, next));
+        suggest(name, candidates)
+    }
+
+    fn match_any(&mut self, token_types: &[TokenType]) -> bool {
+        if token_types.iter().any(|t| self.check(t)) {
+            self.advance();
+            return true;
+        }
+        false
+    }
+
+    fn check(&mut self, token_type: &TokenType) -> bool {
+        if !self.expected.contains(token_type) {
+            self.expected.push(token_type.clone());
+        }
+        !self.is_at_end() && self.peek().token_type == *token_type
+    }
+
+    fn advance(&mut self) -> &Token {
+        if !self.is_at_end() {
+            self.current += 1;
+        }
+        self.expected.clear();
+        self.expected_expression = false;
+        self.previous()
+    }
+
+    // Lists the expected tokens, most specific first: the token a rule was
+    // about to consume is checked for last. Operators are summarized.
+    fn describe_expected(&self) -> String {
+        let mut items: Vec<String> = Vec::new();
+        if self.expected_expression {
+            items.push(String::from("an expression"));
+        }
+        // the token being consumed is named even when it is an operator,
+        // like the `:` of a conditional
+        let (consumed, others) = match self.expected.split_last() {
+            Some((last, others)) if !self.expected_expression => (Some(last), others),
+            _ => (None, &self.expected[..]),
+        };
+        items.extend(consumed.map(TokenType::describe));
+        for token_type in others.iter().rev() {
+            if !token_type.is_operator() {
+                items.push(token_type.describe());
+            }
+        }
+        let any_operator = others.iter().any(TokenType::is_operator);
+        if any_operator && !self.expected_expression {
+            items.push(String::from("an operator"));
+        }
+        match items.split_last() {
+            None => String::new(),
+            Some((last, [])) => last.clone(),
+            Some((last, rest)) => format!("{} or {}", rest.join(", "), last),
+        }
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.peek().token_type == TokenType::EOF
+    }
+
+    fn peek(&self) -> &Token {
+        &self.tokens[self.current]
+    }
+
+    fn previous(&self) -> &Token {
+        &self.tokens[self.current.saturating_sub(1)]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::scanner::Scanner;
+    use crate::token::Dialect;
+
+    fn parse(source_code: &str) -> (Option<Ast>, ErrorHandler) {
+        let mut error_handler = ErrorHandler::new();
+        let tokens = Scanner::new(source_code, &mut error_handler).scan_source();
+        let expr = Parser::new(tokens, &mut error_handler).parse_expression();
+        (expr, error_handler)
+    }
+
+    fn print(source_code: &str) -> String {
+        let (ast, error_handler) = parse(source_code);
+        assert!(!error_handler.has_error(), "{:?}", error_handler);
+        let ast = ast.unwrap();
+        ast.display(ast.root()).to_string()
+    }
+
+    #[test]
+    fn test_literals() {
+        assert_eq!("true", print("true"));
+        assert_eq!("nil", print("nil"));
+        assert_eq!("42.0", print("42"));
+        assert_eq!("hello world", print("\"hello world\""));
+    }
+
+    #[test]
+    fn test_precedence() {
+        assert_eq!("(+ 1.0 (* 2.0 3.0))", print("1 + 2 * 3"));
+        assert_eq!("(- (- 1.0 2.0) 3.0)", print("1 - 2 - 3"));
+        assert_eq!("(* (group (+ 1.0 2.0)) 3.0)", print("(1 + 2) * 3"));
+        assert_eq!("(== (< 1.0 2.0) (! (- 3.0)))", print("1 < 2 == !-3"));
+        assert_eq!("(!= (>= 1.0 2.0) false)", print("1 >= 2 != false"));
+    }
+
+    fn print_extended(source_code: &str) -> String {
+        let mut error_handler = ErrorHandler::new();
+        let tokens = Scanner::new(source_code, &mut error_handler)
+            .with_dialect(Dialect::Extended)
+            .scan_source();
+        let ast = Parser::new(tokens, &mut error_handler).parse_expression();
+        assert!(!error_handler.has_error(), "{:?}", error_handler);
+        let ast = ast.unwrap();
+        ast.display(ast.root()).to_string()
+    }
+
+    #[test]
+    fn test_extended_operators() {
+        assert_eq!("(+ 1.0 (% 2.0 3.0))", print_extended("1 + 2 % 3"));
+        assert_eq!("(** 2.0 (** 3.0 2.0))", print_extended("2 ** 3 ** 2"));
+        assert_eq!("(- (** 2.0 2.0))", print_extended("-2 ** 2"));
+        assert_eq!("(** 2.0 (- 1.0))", print_extended("2 ** -1"));
+        assert_eq!(
+            "(?: (< a b) a (?: c d e))",
+            print_extended("a < b ? a : c ? d : e")
+        );
+        assert_eq!(
+            "(= x (?: (or a b) 1.0 2.0))",
+            print_extended("x = a or b ? 1 : 2")
+        );
+    }
+
+    #[test]
+    fn test_conditional_needs_colon() {
+        let mut error_handler = ErrorHandler::new();
+        let tokens = Scanner::new("a ? b", &mut error_handler)
+            .with_dialect(Dialect::Extended)
+            .scan_source();
+        assert!(Parser::new(tokens, &mut error_handler)
+            .parse_expression()
+            .is_none());
+        assert_eq!(
+            vec!["[line 1] Error at end: Expect ':' after then branch of conditional expression."],
+            error_handler.render_errors("")
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let (expr, error_handler) = parse("(72 +)");
+        assert!(expr.is_none());
+        assert_eq!(
+            vec!["[line 1] Error at ')': Expect expression."],
+            error_handler.render_errors("")
+        );
+
+        let (_, error_handler) = parse("(1");
+        assert_eq!(
+            vec!["[line 1] Error at end: Expect ')' after expression."],
+            error_handler.render_errors("")
+        );
+
+        let (expr, error_handler) = parse("1 2");
+        assert!(expr.is_none());
+        assert_eq!(
+            vec!["[line 1] Error at '2': Expect end of expression."],
+            error_handler.render_errors("")
+        );
+    }
+
+    fn parse_program(source_code: &str) -> (Option<Ast>, ErrorHandler) {
+        let mut error_handler = ErrorHandler::new();
+        let tokens = Scanner::new(source_code, &mut error_handler).scan_source();
+        let statements = Parser::new(tokens, &mut error_handler).parse();
+        (statements, error_handler)
+    }
+
+    fn print_program(source_code: &str) -> Vec<String> {
+        let (ast, error_handler) = parse_program(source_code);
+        assert!(!error_handler.has_error(), "{:?}", error_handler);
+        let ast = ast.unwrap();
+        ast.roots()
+            .iter()
+            .map(|id| ast.display(*id).to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_statements() {
+        assert_eq!(
+            vec![
+                "(var a 1.0)",
+                "(var b)",
+                "(; (= b (+ a 2.0)))",
+                "(print (or (and a b) nil))",
+                "(block (print a) (if (> a 1.0) (print a) (print b)))",
+                "(while true (; (= a (- a 1.0))))",
+            ],
+            print_program(
+                "var a = 1; var b; b = a + 2; print a and b or nil;
+                 { print a; if (a > 1) print a; else print b; }
+                 while (true) a = a - 1;"
+            )
+        );
+    }
+
+    #[test]
+    fn test_for_is_desugared() {
+        assert_eq!(
+            vec!["(block (var i 0.0) (while (< i 3.0) (block (print i) (; (= i (+ i 1.0))))))"],
+            print_program("for (var i = 0; i < 3; i = i + 1) print i;")
+        );
+        assert_eq!(
+            vec!["(while true (print 1.0))"],
+            print_program("for (;;) print 1;")
+        );
+    }
+
+    #[test]
+    fn test_functions_and_classes() {
+        assert_eq!(
+            vec![
+                "(fun add (a b) (return (+ a b)))",
+                "(print (call (call add 1.0 2.0)))",
+                "(class B (< A) (fun init (x) (; (= (. this x) x))) (fun get () (return (call (super get)))))",
+            ],
+            print_program(
+                "fun add(a, b) { return a + b; }
+                 print add(1, 2)();
+                 class B < A {
+                   init(x) { this.x = x; }
+                   get() { return super.get(); }
+                 }"
+            )
+        );
+    }
+
+    #[test]
+    fn test_statement_errors() {
+        let (statements, error_handler) = parse_program("var a = 1\nprint a;");
+        assert!(statements.is_none());
+        assert_eq!(
+            vec!["[line 2] Error at 'print': Expect ';' after variable declaration."],
+            error_handler.render_errors("")
+        );
+
+        let (_, error_handler) = parse_program("retrun x;");
+        assert_eq!(
+            vec!["[line 1] Error at 'x': Expect ';' after expression."],
+            error_handler.render_errors("")
+        );
+
+        // only a statement's first word, followed by what fits the keyword
+        let help = |source_code: &str| -> Vec<String> {
+            let (_, error_handler) = parse_program(source_code);
+            error_handler
+                .diagnostics()
+                .iter()
+                .flat_map(|d| d.help.clone())
+                .collect()
+        };
+        assert_eq!(vec!["did you mean `return`?"], help("retrun x;"));
+        assert_eq!(vec!["did you mean `fun`?"], help("fucn add(a, b) {}"));
+        assert_eq!(vec!["did you mean `class`?"], help("clas A {}"));
+        assert!(help("var i = 1; print i\nprint 2;").is_empty());
+        assert!(help("fun foo() {} foo bar;").is_empty());
+        assert!(help("fo bar;").is_empty());
+        assert!(help("var retrun; retrun x;").is_empty());
+        assert!(help("print retrun x;").is_empty());
+
+        let (_, error_handler) = parse_program("a + b = 3;");
+        assert_eq!(
+            vec!["[line 1] Error at '=': Invalid assignment target."],
+            error_handler.render_errors("")
+        );
+    }
+
+    #[test]
+    fn test_every_error_is_reported() {
+        let (statements, error_handler) = parse_program(
+            "var a = ;
+             print a
+             var b = 2;
+             {
+               print (1 + ;
+               print ok;
+             }
+             class C { m() { 1 + } }
+             print done;
+             fun f() { if (x) { print 1 } print 2; }
+             }
+             print 3",
+        );
+        assert!(statements.is_none());
+        assert_eq!(
+            vec![
+                "[line 1] Error at ';': Expect expression.",
+                "[line 3] Error at 'var': Expect ';' after value.",
+                "[line 5] Error at ';': Expect expression.",
+                "[line 8] Error at '}': Expect expression.",
+                "[line 10] Error at '}': Expect ';' after value.",
+                "[line 11] Error at '}': Expect expression.",
+                "[line 12] Error at end: Expect ';' after value.",
+            ],
+            error_handler.render_errors("")
+        );
+    }
+
+    #[test]
+    fn test_expected_tokens() {
+        let labels = |source_code: &str| -> Vec<Option<String>> {
+            let (_, error_handler) = parse_program(source_code);
+            error_handler
+                .diagnostics()
+                .iter()
+                .map(|d| d.label.clone())
+                .collect()
+        };
+        assert_eq!(
+            vec![Some(String::from(
+                "expected `;`, `.`, `(` or an operator, found `var`"
+            ))],
+            labels("print a\nvar b;")
+        );
+        assert_eq!(
+            vec![Some(String::from("expected an expression, found `)`"))],
+            labels("print (72 +);")
+        );
+        assert_eq!(
+            vec![Some(String::from("expected an identifier, found a number"))],
+            labels("var 1 = 2;")
+        );
+        assert_eq!(
+            vec![Some(String::from(
+                "expected `)`, `.`, `(` or an operator, found end of file"
+            ))],
+            labels("print (1")
+        );
+    }
+}
@@ -0,0 +1,400 @@
+use std::collections::HashMap;
+
+use crate::ast::visitor::{walk_expr, walk_stmt, walk_stmts, Walker};
+use crate::ast::{Ast, Expr, ExprKind, FunctionDecl, NodeMap, Stmt, StmtKind};
+use crate::errors::lint::Lint;
+use crate::errors::{ErrorCode, ErrorHandler};
+use crate::token::Token;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum FunctionKind {
+    None,
+    Function,
+    Initializer,
+    Method,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum ClassKind {
+    None,
+    Class,
+    Subclass,
+}
+
+#[derive(Debug)]
+struct Local {
+    name: Token,
+    // false between declaring a variable and finishing its initializer
+    defined: bool,
+    used: bool,
+    // only `var` declarations are checked by the unused-variables lint
+    is_var: bool,
+}
+
+// Works out, before the program runs, which declaration every variable
+// refers to. Globals are left to be looked up by name at runtime; for locals
+// the resolver records how many scopes out from the reference the variable
+// lives, keyed by the id of the referring expression.
+pub struct Resolver<'a> {
+    error_handler: &'a mut ErrorHandler,
+    scopes: Vec<HashMap<String, Local>>,
+    locals: NodeMap<usize>,
+    function: FunctionKind,
+    class: ClassKind,
+}
+
+impl<'a> Resolver<'a> {
+    pub fn new(error_handler: &'a mut ErrorHandler) -> Self {
+        Resolver {
+            error_handler,
+            scopes: Vec::new(),
+            locals: NodeMap::new(),
+            function: FunctionKind::None,
+            class: ClassKind::None,
+        }
+    }
+
+    pub fn resolve(mut self, ast: &Ast) -> NodeMap<usize> {
+        walk_stmts(&mut self, ast, ast.roots());
+        self.locals
+    }
+
+    fn begin_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    fn end_scope(&mut self) {
+        let Some(scope) = self.scopes.pop() else {
+            return;
+        };
+        let mut unused: Vec<Token> = scope
+            .into_values()
+            .filter(|local| local.is_var && !local.used)
+            .map(|local| local.name)
+            .collect();
+        unused.sort_by_key(|name| (name.line, name.column));
+        for name in unused {
+            self.error_handler.lint(
+                Lint::UnusedVariables,
+                format!("unused variable `{}`", name.lexeme),
+                name.line,
+            );
+        }
+    }
+
+    fn declare(&mut self, name: &Token, is_var: bool) {
+        let Some((scope, enclosing)) = self.scopes.split_last_mut() else {
+            return;
+        };
+        if scope.contains_key(&name.lexeme) {
+            self.error_handler.report_token(
+                ErrorCode::Redeclaration,
+                String::from("Already a variable with this name in this scope."),
+                name,
+            );
+            return;
+        }
+        if enclosing
+            .iter()
+            .any(|scope| scope.contains_key(&name.lexeme))
+        {
+            self.error_handler.lint(
+                Lint::Shadowing,
+                format!("`{}` shadows a variable in an enclosing scope", name.lexeme),
+                name.line,
+            );
+        }
+        scope.insert(
+            name.lexeme.clone(),
+            Local {
+                name: name.clone(),
+                defined: false,
+                used: false,
+                is_var,
+            },
+        );
+    }
+
+    fn define(&mut self, name: &Token) {
+        if let Some(local) = self
+            .scopes
+            .last_mut()
+            .and_then(|scope| scope.get_mut(&name.lexeme))
+        {
+            local.defined = true;
+        }
+    }
+
+    // Declares a name that needs no initializer, such as a parameter.
+    fn declare_defined(&mut self, name: &Token) {
+        self.declare(name, false);
+        self.define(name);
+    }
+
+    // Binds `this` or `super` in the current scope, for the methods of the
+    // class `class`.
+    fn bind_keyword(&mut self, keyword: &str, class: &Token) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(
+                String::from(keyword),
+                Local {
+                    name: class.clone(),
+                    defined: true,
+                    used: true,
+                    is_var: false,
+                },
+            );
+        }
+    }
+
+    fn resolve_local(&mut self, expr: &Expr, name: &Token, is_read: bool) {
+        for (depth, scope) in self.scopes.iter_mut().rev().enumerate() {
+            if let Some(local) = scope.get_mut(&name.lexeme) {
+                local.used |= is_read;
+                self.locals.insert(expr.id, depth);
+                return;
+            }
+        }
+    }
+
+    fn resolve_function(&mut self, ast: &Ast, function: &FunctionDecl, kind: FunctionKind) {
+        let enclosing = std::mem::replace(&mut self.function, kind);
+        self.begin_scope();
+        for param in &function.params {
+            self.declare_defined(param);
+        }
+        walk_stmts(self, ast, &function.body);
+        self.end_scope();
+        self.function = enclosing;
+    }
+}
+
+impl Walker for Resolver<'_> {
+    fn visit_stmt(&mut self, ast: &Ast, stmt: &Stmt) {
+        match &stmt.kind {
+            StmtKind::Block { statements } => {
+                self.begin_scope();
+                walk_stmts(self, ast, statements);
+                self.end_scope();
+            }
+            StmtKind::Var { name, initializer } => {
+                self.declare(name, true);
+                if let Some(initializer) = initializer {
+                    self.visit_expr(ast, ast.expr(*initializer));
+                }
+                self.define(name);
+            }
+            // Defined before the body so the function can call itself.
+            StmtKind::Function(function) => {
+                self.declare_defined(&function.name);
+                self.resolve_function(ast, function, FunctionKind::Function);
+            }
+            StmtKind::Return { keyword, value } => {
+                if self.function == FunctionKind::None {
+                    self.error_handler.report_token(
+                        ErrorCode::ReturnOutsideFunction,
+                        String::from("Can't return from top-level code."),
+                        keyword,
+                    );
+                }
+                if let Some(value) = value {
+                    if self.function == FunctionKind::Initializer {
+                        self.error_handler.report_token(
+                            ErrorCode::ReturnFromInitializer,
+                            String::from("Can't return a value from an initializer."),
+                            keyword,
+                        );
+                    }
+                    self.visit_expr(ast, ast.expr(*value));
+                }
+            }
+            StmtKind::Class {
+                name,
+                superclass,
+                methods,
+            } => {
+                let enclosing = std::mem::replace(&mut self.class, ClassKind::Class);
+                self.declare_defined(name);
+                if let Some(superclass) = superclass.map(|id| ast.expr(id)) {
+                    if let ExprKind::Variable {
+                        name: superclass_name,
+                    } = &superclass.kind
+                    {
+                        if superclass_name.lexeme == name.lexeme {
+                            self.error_handler.report_token(
+                                ErrorCode::InheritFromSelf,
+                                String::from("A class can't inherit from itself."),
+                                superclass_name,
+                            );
+                        }
+                    }
+                    self.class = ClassKind::Subclass;
+                    self.visit_expr(ast, superclass);
+                    self.begin_scope();
+                    self.bind_keyword("super", name);
+                }
+                self.begin_scope();
+                self.bind_keyword("this", name);
+                for method in methods {
+                    let kind = match method.name.lexeme.as_str() {
+                        "init" => FunctionKind::Initializer,
+                        _ => FunctionKind::Method,
+                    };
+                    self.resolve_function(ast, method, kind);
+                }
+                self.end_scope();
+                if superclass.is_some() {
+                    self.end_scope();
+                }
+                self.class = enclosing;
+            }
+            _ => walk_stmt(self, ast, stmt),
+        }
+    }
+
+    fn visit_expr(&mut self, ast: &Ast, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Variable { name } => {
+                let in_own_initializer = self
+                    .scopes
+                    .last()
+                    .and_then(|scope| scope.get(&name.lexeme))
+                    .is_some_and(|local| !local.defined);
+                if in_own_initializer {
+                    self.error_handler.report_token(
+                        ErrorCode::ReadInOwnInitializer,
+                        String::from("Can't read local variable in its own initializer."),
+                        name,
+                    );
+                }
+                self.resolve_local(expr, name, true);
+            }
+            ExprKind::Assign { name, value } => {
+                self.visit_expr(ast, ast.expr(*value));
+                self.resolve_local(expr, name, false);
+            }
+            ExprKind::This { keyword } => {
+                if self.class == ClassKind::None {
+                    self.error_handler.report_token(
+                        ErrorCode::ThisOutsideClass,
+                        String::from("Can't use 'this' outside of a class."),
+                        keyword,
+                    );
+                    return;
+                }
+                self.resolve_local(expr, keyword, true);
+            }
+            ExprKind::Super { keyword, .. } => match self.class {
+                ClassKind::None => self.error_handler.report_token(
+                    ErrorCode::SuperOutsideClass,
+                    String::from("Can't use 'super' outside of a class."),
+                    keyword,
+                ),
+                ClassKind::Class => self.error_handler.report_token(
+                    ErrorCode::SuperWithoutSuperclass,
+                    String::from("Can't use 'super' in a class with no superclass."),
+                    keyword,
+                ),
+                ClassKind::Subclass => self.resolve_local(expr, keyword, true),
+            },
+            _ => walk_expr(self, ast, expr),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::errors::lint::LintConfig;
+    use crate::parse_program;
+
+    fn resolve(source_code: &str, lints: LintConfig) -> (NodeMap<usize>, Vec<String>) {
+        let ast = parse_program(source_code).ast.unwrap();
+        let mut error_handler = ErrorHandler::new();
+        error_handler.set_lints(lints);
+        let locals = Resolver::new(&mut error_handler).resolve(&ast);
+        (locals, error_handler.render_errors(source_code))
+    }
+
+    fn errors(source_code: &str) -> Vec<String> {
+        let mut lints = LintConfig::new();
+        lints.apply_flag("-A", "warnings").unwrap();
+        resolve(source_code, lints).1
+    }
+
+    #[test]
+    fn test_depths() {
+        let (locals, errors) = resolve(
+            "var g = 1; { var a = 1; { var b = a + g; print b; } print a; }",
+            LintConfig::new(),
+        );
+        assert!(errors.is_empty());
+        // `a` in `a + g`, one scope out; `b` in `print b`; `a` in `print a`
+        let depths: Vec<usize> = locals.iter().map(|(_, depth)| *depth).collect();
+        assert_eq!(vec![1, 0, 0], depths);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            vec!["[line 1] Error at 'a': Can't read local variable in its own initializer."],
+            errors("var a = 1; { var a = a; print a; }")
+        );
+        assert!(errors("var a = 1; var a = a;").is_empty());
+        assert_eq!(
+            vec!["[line 1] Error at 'a': Already a variable with this name in this scope."],
+            errors("fun f() { var a = 1; var a = 2; }")
+        );
+        assert_eq!(
+            vec!["[line 1] Error at 'return': Can't return from top-level code."],
+            errors("return 1;")
+        );
+        assert!(errors("fun f() { return 1; }").is_empty());
+        assert_eq!(
+            vec!["[line 1] Error at 'this': Can't use 'this' outside of a class."],
+            errors("fun f() { print this; }")
+        );
+        assert_eq!(
+            vec!["[line 1] Error at 'super': Can't use 'super' outside of a class."],
+            errors("print super.x;")
+        );
+        assert!(errors("class A { f() { return this; } }").is_empty());
+        assert_eq!(
+            vec!["[line 1] Error at 'return': Can't return a value from an initializer."],
+            errors("class A { init() { return 1; } }")
+        );
+        assert!(errors("class A { init() { return; } }").is_empty());
+        assert_eq!(
+            vec!["[line 1] Error at 'A': A class can't inherit from itself."],
+            errors("class A < A {}")
+        );
+        assert_eq!(
+            vec!["[line 1] Error at 'super': Can't use 'super' in a class with no superclass."],
+            errors("class A { f() { super.f(); } }")
+        );
+        assert!(errors("class A {} class B < A { f() { super.f(); } }").is_empty());
+    }
+
+    #[test]
+    fn test_lints() {
+        let source = "fun f(x) { var used = x; var unused = 2; unused = 3; return used; }";
+        let (_, warnings) = resolve(source, LintConfig::new());
+        assert!(warnings.is_empty());
+        let mut lints = LintConfig::new();
+        lints.apply_flag("-W", "unused-variables").unwrap();
+        let (_, warnings) = resolve(source, lints);
+        assert_eq!(vec!["[line 1] Warning: unused variable `unused`"], warnings);
+
+        let mut lints = LintConfig::new();
+        lints.apply_flag("-W", "shadowing").unwrap();
+        lints.apply_flag("-A", "unused-variables").unwrap();
+        let (_, warnings) = resolve(
+            "var total; { var total = 1; { var total = 2; print total; } }",
+            lints,
+        );
+        assert_eq!(
+            vec!["[line 1] Warning: `total` shadows a variable in an enclosing scope"],
+            warnings
+        );
+    }
+}
@@ -1,12 +1,16 @@
-use crate::errors::ErrorHandler;
+use crate::errors::lint::{confusable_with, Lint};
+use crate::errors::suggest::did_you_mean;
+use crate::errors::{ErrorCode, ErrorHandler};
 use crate::superiterator::SuperIterator;
 use crate::token::number::Number;
-use crate::token::{Token, TokenType};
+use crate::token::{Dialect, Span, Token, TokenType};
 
 pub struct Scanner<'a> {
     pub source_code: Vec<char>,
     curr_idx: usize,
     curr_line: usize,
+    line_start: usize,
+    dialect: Dialect,
     error_handler: &'a mut ErrorHandler,
 }
 
@@ -16,17 +20,26 @@ impl<'a> Scanner<'a> {
             source_code: source_code.chars().collect(),
             curr_idx: 0,
             curr_line: 1,
+            line_start: 0,
+            dialect: Dialect::default(),
             error_handler,
         }
     }
 
+    pub fn with_dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
     pub fn scan_source(&mut self) -> Vec<Token> {
         let mut tokens: Vec<Token> = Vec::new();
         while let Some(c) = self.next() {
             if c == '\n' {
-                self.curr_line += 1;
+                self.new_line();
                 continue;
             }
+            let start = self.curr_idx - 1;
+            let (start_line, start_column) = (self.curr_line, start - self.line_start + 1);
             let token_type: TokenType = match c {
                 '=' => {
                     if self.is_next('=') {
@@ -60,6 +73,14 @@ impl<'a> Scanner<'a> {
                         TokenType::Greater
                     }
                 }
+                '*' => {
+                    if self.dialect == Dialect::Extended && self.is_next('*') {
+                        self.next();
+                        TokenType::StarStar
+                    } else {
+                        TokenType::Star
+                    }
+                }
                 '/' => {
                     if self.is_next('/') {
                         self.read_until('\n');
@@ -84,7 +105,9 @@ impl<'a> Scanner<'a> {
                     let mut token = TokenType::None;
                     // check if single character is part of language
                     if let Some(t) = self.get_from_factory(other_char.to_string()) {
-                        token = t;
+                        if t.is_available_in(self.dialect) {
+                            token = t;
+                        }
                     } else {
                         // handle other cases
                         if other_char.is_numeric() {
@@ -98,28 +121,50 @@ impl<'a> Scanner<'a> {
                 }
             };
             if token_type != TokenType::None {
-                tokens.push(Token::new(token_type, self.curr_line));
+                let lexeme: String = self.source_code[start..self.curr_idx].iter().collect();
+                tokens.push(Token::new(token_type, start_line).at(
+                    lexeme,
+                    start_column,
+                    Span::new(start, self.curr_idx),
+                ));
             } else {
+                let column = self.curr_idx - self.line_start;
                 self.error_handler
-                    .report(format!("Unexpected character: {}", c), self.curr_line);
+                    .report_unexpected_char(c, self.curr_line, column);
+                // operators borrowed from C-like languages have keyword spellings in Lox
+                match c {
+                    '&' => self.error_handler.add_help(did_you_mean("and")),
+                    '|' => self.error_handler.add_help(did_you_mean("or")),
+                    _ => {}
+                }
                 // println!("Got none token type");
             }
         }
-        tokens.push(Token::new(TokenType::EOF, self.curr_line));
+        let end = self.source_code.len();
+        tokens.push(Token::new(TokenType::EOF, self.curr_line).at(
+            String::new(),
+            end - self.line_start + 1,
+            Span::new(end, end),
+        ));
         tokens
     }
 
     fn get_string_token(&mut self) -> Option<TokenType> {
         let mut string_str = String::new();
-        for c in &mut *self {
-            // while let Some(c) = self.next() {
+        while let Some(c) = self.next() {
             if c == '"' {
                 return Some(TokenType::String(string_str));
             }
             string_str.push(c);
+            if c == '\n' {
+                self.new_line();
+            }
         }
-        self.error_handler
-            .report(String::from("Unterminated string."), self.curr_line);
+        self.error_handler.report(
+            ErrorCode::UnterminatedString,
+            String::from("Unterminated string."),
+            self.curr_line,
+        );
         None
     }
 
@@ -137,7 +182,34 @@ impl<'a> Scanner<'a> {
 
         match keyword_str.parse::<TokenType>() {
             Ok(kw) => kw,
-            Err(_) => TokenType::Identifier(keyword_str),
+            Err(_) => {
+                self.check_confusables(&keyword_str);
+                TokenType::Identifier(keyword_str)
+            }
+        }
+    }
+
+    fn check_confusables(&mut self, ident: &str) {
+        let Some((c, ascii)) = ident
+            .chars()
+            .find_map(|c| confusable_with(c).map(|ascii| (c, ascii)))
+        else {
+            return;
+        };
+        self.error_handler.lint(
+            Lint::ConfusableIdentifiers,
+            format!(
+                "identifier `{}` contains `{}` (U+{:04X}), which looks like `{}`",
+                ident, c, c as u32, ascii
+            ),
+            self.curr_line,
+        );
+        let respelled: String = ident
+            .chars()
+            .map(|c| confusable_with(c).unwrap_or(c))
+            .collect();
+        if respelled.is_ascii() {
+            self.error_handler.add_help(did_you_mean(&respelled));
         }
     }
 
@@ -155,23 +227,29 @@ impl<'a> Scanner<'a> {
         match number_str.parse::<Number>() {
             Ok(n) => Some(TokenType::Number(n, number_str)),
             Err(_) => {
-                self.error_handler
-                    .report(String::from("impossible to parse a number"), self.curr_line);
+                self.error_handler.report(
+                    ErrorCode::InvalidNumber,
+                    String::from("impossible to parse a number"),
+                    self.curr_line,
+                );
                 None
             }
         }
     }
     fn read_until(&mut self, end: char) {
-        let mut line_count = self.curr_line;
-        for c in &mut *self {
+        while let Some(c) = self.next() {
             if c == '\n' {
-                line_count += 1;
+                self.new_line();
             }
             if c == end {
                 break;
             }
         }
-        self.curr_line = line_count;
+    }
+
+    fn new_line(&mut self) {
+        self.curr_line += 1;
+        self.line_start = self.curr_idx;
     }
 
     fn is_next(&self, c: char) -> bool {
@@ -338,4 +416,112 @@ var y = x + 8.8;
         let tokens = scanner.scan_source();
         assert_eq!(tokens.len(), 13);
     }
+
+    #[test]
+    fn test_unexpected_character_runs() {
+        let mut error_handler = ErrorHandler::new();
+        let source_code = "(\n,$#@.\n\"a\nb\" %";
+        let mut scanner = Scanner::new(source_code, &mut error_handler);
+        scanner.scan_source();
+        assert_eq!(
+            vec![
+                "[line 2] Error: Unexpected characters: $#@ (columns 2-4)",
+                "[line 4] Error: Unexpected character: %",
+            ],
+            error_handler.render_errors(source_code)
+        );
+    }
+
+    #[test]
+    fn test_c_style_operators_get_help() {
+        let mut error_handler = ErrorHandler::new();
+        let source_code = "a && b";
+        let mut scanner = Scanner::new(source_code, &mut error_handler);
+        scanner.scan_source();
+        assert_eq!(
+            vec!["[line 1] Error: Unexpected characters: && (columns 3-4)"],
+            error_handler.render_errors(source_code)
+        );
+        assert_eq!(
+            vec![String::from("did you mean `and`?")],
+            error_handler.diagnostics()[0].help
+        );
+    }
+
+    #[test]
+    fn test_confusable_identifier_warning() {
+        let mut error_handler = ErrorHandler::new();
+        let source_code = "var p\u{0430}y = 1;";
+        let mut scanner = Scanner::new(source_code, &mut error_handler);
+        scanner.scan_source();
+        assert!(!error_handler.has_error());
+        assert_eq!(
+            vec!["[line 1] Warning: identifier `p\u{0430}y` contains `\u{0430}` (U+0430), which looks like `a`"],
+            error_handler.render_errors(source_code)
+        );
+        assert_eq!(
+            vec![String::from("did you mean `pay`?")],
+            error_handler.diagnostics()[0].help
+        );
+    }
+
+    #[test]
+    fn test_extended_dialect() {
+        let mut error_handler = ErrorHandler::new();
+        let source_code = "a % b ** c ? d : e";
+        let mut scanner =
+            Scanner::new(source_code, &mut error_handler).with_dialect(Dialect::Extended);
+        let got: Vec<TokenType> = scanner
+            .scan_source()
+            .into_iter()
+            .map(|token| token.token_type)
+            .collect();
+        assert_eq!(
+            vec![
+                TokenType::Identifier(String::from("a")),
+                TokenType::Percent,
+                TokenType::Identifier(String::from("b")),
+                TokenType::StarStar,
+                TokenType::Identifier(String::from("c")),
+                TokenType::Question,
+                TokenType::Identifier(String::from("d")),
+                TokenType::Colon,
+                TokenType::Identifier(String::from("e")),
+                TokenType::EOF,
+            ],
+            got
+        );
+
+        let mut error_handler = ErrorHandler::new();
+        let mut scanner = Scanner::new("a % b ** c", &mut error_handler);
+        let tokens = scanner.scan_source();
+        assert_eq!(TokenType::Star, tokens[2].token_type);
+        assert_eq!(TokenType::Star, tokens[3].token_type);
+        assert_eq!(vec![ErrorCode::UnexpectedCharacter], error_handler.codes());
+    }
+
+    #[test]
+    fn test_token_positions() {
+        let mut error_handler = ErrorHandler::new();
+        let source_code = "var s =\n  \"a\nb\" >= 12.5;";
+        let mut scanner = Scanner::new(source_code, &mut error_handler);
+        let tokens = scanner.scan_source();
+        let positions: Vec<(&str, usize, usize, Span)> = tokens
+            .iter()
+            .map(|t| (t.lexeme.as_str(), t.line, t.column, t.span))
+            .collect();
+        assert_eq!(
+            vec![
+                ("var", 1, 1, Span::new(0, 3)),
+                ("s", 1, 5, Span::new(4, 5)),
+                ("=", 1, 7, Span::new(6, 7)),
+                ("\"a\nb\"", 2, 3, Span::new(10, 15)),
+                (">=", 3, 4, Span::new(16, 18)),
+                ("12.5", 3, 7, Span::new(19, 23)),
+                (";", 3, 11, Span::new(23, 24)),
+                ("", 3, 12, Span::new(24, 24)),
+            ],
+            positions
+        );
+    }
 }
@@ -1,3 +1,4 @@
+pub mod format;
 pub mod number;
 
 use maplit::hashmap;
@@ -29,6 +30,11 @@ pub enum TokenType {
     Star,
     Slash,
     Equal,
+    // operators of the extended dialect
+    Percent,
+    StarStar,
+    Question,
+    Colon,
 
     EqualEqual,
     Bang,
@@ -70,6 +76,10 @@ static LEXEME_TO_TOKENTYPE: Lazy<HashMap<&'static str, TokenType>> = Lazy::new(|
         "*" => TokenType::Star,
         "/" => TokenType::Slash,
         "=" => TokenType::Equal,
+        "%" => TokenType::Percent,
+        "**" => TokenType::StarStar,
+        "?" => TokenType::Question,
+        ":" => TokenType::Colon,
         "==" => TokenType::EqualEqual,
         "!" => TokenType::Bang,
         "!=" => TokenType::BangEqual,
@@ -111,6 +121,10 @@ static TOKENTYPE_TO_STRING: Lazy<HashMap<TokenType, &'static str>> = Lazy::new(|
         TokenType::Star     =>"STAR",
         TokenType::Slash => "SLASH",
         TokenType::Equal => "EQUAL",
+        TokenType::Percent => "PERCENT",
+        TokenType::StarStar => "STAR_STAR",
+        TokenType::Question => "QUESTION",
+        TokenType::Colon => "COLON",
         TokenType::EqualEqual => "EQUAL_EQUAL",
         TokenType::Bang => "BANG",
         TokenType::BangEqual => "BANG_EQUAL",
@@ -139,7 +153,104 @@ static TOKENTYPE_TO_STRING: Lazy<HashMap<TokenType, &'static str>> = Lazy::new(|
     }
 });
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Dialect {
+    // the language exactly as described in Crafting Interpreters
+    #[default]
+    Lox,
+    // adds `%`, `**` and the `?:` conditional operator
+    Extended,
+}
+
+impl FromStr for Dialect {
+    type Err = String;
+    fn from_str(dialect: &str) -> Result<Self, Self::Err> {
+        match dialect {
+            "lox" => Ok(Dialect::Lox),
+            "extended" => Ok(Dialect::Extended),
+            other => Err(format!(
+                "unknown dialect: {} (expected lox or extended)",
+                other
+            )),
+        }
+    }
+}
+
+pub fn keywords() -> Vec<&'static str> {
+    let mut keywords: Vec<&'static str> = LEXEME_TO_TOKENTYPE
+        .keys()
+        .filter(|lexeme| lexeme.chars().all(|c| c.is_alphabetic()) && !lexeme.is_empty())
+        .copied()
+        .collect();
+    keywords.sort();
+    keywords
+}
+
 impl TokenType {
+    // The CodeCrafters name of the token kind, e.g. `LEFT_PAREN`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            TokenType::None => "NONE",
+            TokenType::String(_) => "STRING",
+            TokenType::Number(..) => "NUMBER",
+            TokenType::Identifier(_) => "IDENTIFIER",
+            other => TOKENTYPE_TO_STRING.get(other).copied().unwrap_or("NONE"),
+        }
+    }
+
+    pub fn literal(&self) -> Option<String> {
+        match self {
+            TokenType::String(value) => Some(value.clone()),
+            TokenType::Number(num, _) => Some(num.to_string()),
+            _ => None,
+        }
+    }
+
+    // How the kind reads in a message: `` `;` `` or "an identifier".
+    pub fn describe(&self) -> String {
+        match self {
+            TokenType::None => String::from("an invalid token"),
+            TokenType::EOF => String::from("end of file"),
+            TokenType::String(_) => String::from("a string"),
+            TokenType::Number(..) => String::from("a number"),
+            TokenType::Identifier(_) => String::from("an identifier"),
+            other => format!("`{}`", other.to_str()),
+        }
+    }
+
+    pub fn is_operator(&self) -> bool {
+        matches!(
+            self,
+            TokenType::Minus
+                | TokenType::Plus
+                | TokenType::Star
+                | TokenType::Slash
+                | TokenType::Equal
+                | TokenType::EqualEqual
+                | TokenType::Bang
+                | TokenType::BangEqual
+                | TokenType::Less
+                | TokenType::LessEqual
+                | TokenType::Greater
+                | TokenType::GreaterEqual
+                | TokenType::And
+                | TokenType::Or
+                | TokenType::Percent
+                | TokenType::StarStar
+                | TokenType::Question
+                | TokenType::Colon
+        )
+    }
+
+    pub fn is_available_in(&self, dialect: Dialect) -> bool {
+        match self {
+            TokenType::Percent | TokenType::StarStar | TokenType::Question | TokenType::Colon => {
+                dialect == Dialect::Extended
+            }
+            _ => true,
+        }
+    }
+
     pub fn to_str(&self) -> String {
         match self {
             TokenType::String(value) => value.clone(),
@@ -186,19 +297,62 @@ impl Display for TokenType {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+// Half-open range [start, end) of character offsets into the source.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Span { start, end }
+    }
+
+    pub fn to(&self, other: Span) -> Span {
+        Span::new(self.start.min(other.start), self.end.max(other.end))
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Token {
     pub token_type: TokenType,
     pub line: usize,
-    // pub lexeme: Option<String>,
+    pub lexeme: String,
+    // 1-based column of the first character of the lexeme
+    pub column: usize,
+    pub span: Span,
 }
 
 impl Token {
     pub fn new(token_type: TokenType, line: usize) -> Self {
-        Token { token_type, line }
+        Token {
+            token_type,
+            line,
+            lexeme: String::new(),
+            column: 0,
+            span: Span::default(),
+        }
+    }
+
+    pub fn at(mut self, lexeme: String, column: usize, span: Span) -> Self {
+        self.lexeme = lexeme;
+        self.column = column;
+        self.span = span;
+        self
     }
 }
 
+// Tokens compare by kind and line only; where exactly the lexeme sits is
+// not part of a token's identity.
+impl PartialEq for Token {
+    fn eq(&self, other: &Self) -> bool {
+        self.token_type == other.token_type && self.line == other.line
+    }
+}
+
+impl Eq for Token {}
+
 impl Display for Token {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "token_type: {}\nline: {}", self.token_type, self.line)
@@ -232,6 +386,12 @@ mod test {
             )
         );
         println!("{}", Number::from_str("90.1").unwrap());
+        assert_eq!("`;`", TokenType::Semicolon.describe());
+        assert_eq!("`while`", TokenType::While.describe());
+        assert_eq!(
+            "an identifier",
+            TokenType::Identifier(String::from("x")).describe()
+        );
         assert_eq!(String::from("EOF  null"), format!("{}", TokenType::EOF));
         assert_eq!(
             String::from("SEMICOLON ; null"),
@@ -0,0 +1,232 @@
+use std::fmt;
+use std::fmt::Display;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use super::number::Number;
+use super::{Span, Token, TokenType};
+use crate::json::Json;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum TokenFormat {
+    // `KIND lexeme literal`, the format the CodeCrafters tests expect
+    #[default]
+    Text,
+    Json,
+    Ndjson,
+    Table,
+}
+
+impl FromStr for TokenFormat {
+    type Err = String;
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "text" => Ok(TokenFormat::Text),
+            "json" => Ok(TokenFormat::Json),
+            "ndjson" => Ok(TokenFormat::Ndjson),
+            "table" => Ok(TokenFormat::Table),
+            other => Err(format!(
+                "unknown token format: {} (expected text, json, ndjson or table)",
+                other
+            )),
+        }
+    }
+}
+
+impl Display for TokenFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let format = match self {
+            TokenFormat::Text => "text",
+            TokenFormat::Json => "json",
+            TokenFormat::Ndjson => "ndjson",
+            TokenFormat::Table => "table",
+        };
+        write!(f, "{}", format)
+    }
+}
+
+impl Token {
+    pub fn to_json(&self) -> Json {
+        Json::object(vec![
+            ("kind", Json::string(self.token_type.kind())),
+            ("lexeme", Json::string(self.lexeme.as_str())),
+            (
+                "literal",
+                self.token_type.literal().map_or(Json::Null, Json::String),
+            ),
+            ("line", Json::Number(self.line as f64)),
+            ("column", Json::Number(self.column as f64)),
+            (
+                "span",
+                Json::Array(vec![
+                    Json::Number(self.span.start as f64),
+                    Json::Number(self.span.end as f64),
+                ]),
+            ),
+        ])
+    }
+
+    // The inverse of `to_json`, for reading tokens back out of fixtures.
+    pub fn from_json(json: &Json) -> Result<Token, String> {
+        let field = |name: &str| json.get(name).ok_or(format!("token is missing `{}`", name));
+        let text = |name: &str| {
+            field(name)?
+                .as_str()
+                .ok_or(format!("token field `{}` must be a string", name))
+        };
+        let position = |value: &Json| {
+            value
+                .as_f64()
+                .map(|n| n as usize)
+                .ok_or(String::from("token positions must be numbers"))
+        };
+        let kind = text("kind")?;
+        let lexeme = text("lexeme")?;
+        let token_type = match kind {
+            "STRING" => TokenType::String(text("literal")?.to_string()),
+            "NUMBER" => {
+                let number = lexeme
+                    .parse::<Number>()
+                    .map_err(|e| format!("invalid number `{}`: {}", lexeme, e))?;
+                TokenType::Number(number, lexeme.to_string())
+            }
+            "IDENTIFIER" => TokenType::Identifier(lexeme.to_string()),
+            _ => lexeme
+                .parse::<TokenType>()
+                .ok()
+                .filter(|token_type| token_type.kind() == kind)
+                .ok_or(format!("unknown token `{}` of kind {}", lexeme, kind))?,
+        };
+        let span = match field("span")?.as_array() {
+            Some([start, end]) => Span::new(position(start)?, position(end)?),
+            _ => return Err(String::from("token span must be a [start, end] pair")),
+        };
+        Ok(Token::new(token_type, position(field("line")?)?).at(
+            lexeme.to_string(),
+            position(field("column")?)?,
+            span,
+        ))
+    }
+}
+
+pub fn write_tokens<W: Write>(
+    out: &mut W,
+    tokens: &[Token],
+    format: TokenFormat,
+) -> io::Result<()> {
+    match format {
+        TokenFormat::Text => {
+            for token in tokens {
+                writeln!(out, "{}", token.token_type)?;
+            }
+        }
+        TokenFormat::Json => {
+            let tokens = Json::Array(tokens.iter().map(Token::to_json).collect());
+            writeln!(out, "{}", tokens.pretty())?;
+        }
+        TokenFormat::Ndjson => {
+            for token in tokens {
+                writeln!(out, "{}", token.to_json())?;
+            }
+        }
+        TokenFormat::Table => write_table(out, tokens)?,
+    }
+    Ok(())
+}
+
+fn write_table<W: Write>(out: &mut W, tokens: &[Token]) -> io::Result<()> {
+    let header = ["KIND", "LEXEME", "LITERAL", "LINE", "COLUMN", "SPAN"];
+    let rows: Vec<[String; 6]> = tokens
+        .iter()
+        .map(|token| {
+            [
+                token.token_type.kind().to_string(),
+                token.lexeme.escape_debug().to_string(),
+                token
+                    .token_type
+                    .literal()
+                    .map_or(String::from("null"), |l| l.escape_debug().to_string()),
+                token.line.to_string(),
+                token.column.to_string(),
+                format!("{}..{}", token.span.start, token.span.end),
+            ]
+        })
+        .collect();
+    let mut widths = header.map(|h| h.chars().count());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let header = header.map(String::from);
+    for row in std::iter::once(&header).chain(&rows) {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        writeln!(out, "{}", cells.join("  ").trim_end())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::errors::ErrorHandler;
+    use crate::scanner::Scanner;
+
+    fn render(source_code: &str, format: TokenFormat) -> String {
+        let mut error_handler = ErrorHandler::new();
+        let tokens = Scanner::new(source_code, &mut error_handler).scan_source();
+        let mut out = Vec::new();
+        write_tokens(&mut out, &tokens, format).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_text_is_unchanged() {
+        assert_eq!(
+            "VAR var null\nSTRING \"hi\" hi\nEOF  null\n",
+            render("var \"hi\"", TokenFormat::Text)
+        );
+    }
+
+    #[test]
+    fn test_ndjson() {
+        assert_eq!(
+            "{\"kind\":\"NUMBER\",\"lexeme\":\"12\",\"literal\":\"12.0\",\"line\":1,\"column\":1,\"span\":[0,2]}\n\
+             {\"kind\":\"EOF\",\"lexeme\":\"\",\"literal\":null,\"line\":1,\"column\":3,\"span\":[2,2]}\n",
+            render("12", TokenFormat::Ndjson)
+        );
+    }
+
+    #[test]
+    fn test_table() {
+        assert_eq!(
+            "KIND        LEXEME  LITERAL  LINE  COLUMN  SPAN\n\
+             IDENTIFIER  foo     null     1     1       0..3\n\
+             EOF                 null     1     4       3..3\n",
+            render("foo", TokenFormat::Table)
+        );
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let mut error_handler = ErrorHandler::new();
+        let tokens = Scanner::new("var s = \"a\nb\" + 1.50;", &mut error_handler).scan_source();
+        for token in tokens {
+            let read = Token::from_json(&token.to_json()).unwrap();
+            assert_eq!(token, read);
+            assert_eq!(
+                (&token.lexeme, token.column, token.span),
+                (&read.lexeme, read.column, read.span)
+            );
+        }
+        let bogus = Json::object(vec![
+            ("kind", Json::string("PLUS")),
+            ("lexeme", Json::string("-")),
+        ]);
+        assert!(Token::from_json(&bogus).is_err());
+    }
+}
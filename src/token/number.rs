@@ -25,6 +25,12 @@ impl FromStr for Number {
     }
 }
 
+impl From<f64> for Number {
+    fn from(value: f64) -> Self {
+        Number { value }
+    }
+}
+
 impl PartialEq for Number {
     fn eq(&self, other: &Self) -> bool {
         if self.value.is_nan() || other.value.is_nan() {